@@ -0,0 +1,168 @@
+use std::io::{Read, Seek};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::ParserBeatmap;
+use crate::{replay::ParserResult, utils::read_zip_entries};
+
+const SOUND_EXTENSIONS: [&str; 3] = ["wav", "ogg", "mp3"];
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum BeatmapsetFileKind
+{
+    Beatmap,
+    Audio,
+    Background,
+    Video,
+    Storyboard,
+    Hitsound,
+    #[default]
+    Other,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BeatmapsetFile
+{
+    /// Path of the file inside the archive.
+    pub name: String,
+    pub size: u64,
+    /// MD5 hash of the file, for .osu files this is the same as [`ParserBeatmap::hash`].
+    pub hash: String,
+    pub kind: BeatmapsetFileKind,
+}
+
+/// Difficulty of a beatmapset which could not be parsed.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BeatmapsetError
+{
+    /// Path of the .osu file inside the archive.
+    pub name:  String,
+    pub error: String,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserBeatmapset
+{
+    pub beatmaps:        Vec<ParserBeatmap>,
+    /// Difficulties which are left out of `beatmaps`, since they could not be parsed.
+    pub failed_beatmaps: Vec<BeatmapsetError>,
+    pub files:           Vec<BeatmapsetFile>,
+    /// Audio, background and video files referenced by a difficulty which are not in the archive.
+    pub missing_files:   Vec<String>,
+}
+
+impl ParserBeatmapset
+{
+    /// Parses every difficulty of an .osz archive and lists the files it contains.
+    ///
+    /// Difficulties which fail to parse are reported in `failed_beatmaps` instead of failing the
+    /// whole beatmapset.
+    pub fn parse<R: Read + Seek>(osz: R) -> ParserResult<Self>
+    {
+        let entries = read_zip_entries(osz)?;
+
+        let mut beatmaps = Vec::new();
+        let mut failed_beatmaps = Vec::new();
+        for (name, data) in entries.iter().filter(|(name, _)| extension(name) == "osu")
+        {
+            match ParserBeatmap::parse(&mut data.as_slice())
+            {
+                Ok(beatmap) => beatmaps.push(beatmap),
+                Err(error) => failed_beatmaps.push(BeatmapsetError {
+                    name:  name.clone(),
+                    error: error.to_string(),
+                }),
+            }
+        }
+
+        let referenced = |filename: fn(&ParserBeatmap) -> Option<&String>| {
+            beatmaps
+                .iter()
+                .filter_map(filename)
+                .map(|name| normalize(name))
+                .unique()
+                .collect_vec()
+        };
+        let audio = referenced(|beatmap| Some(&beatmap.audio_filename));
+        let backgrounds = referenced(|beatmap| beatmap.background.as_ref());
+        let videos = referenced(|beatmap| beatmap.video.as_ref());
+
+        let files = entries
+            .iter()
+            .map(|(name, data)| {
+                let normalized = normalize(name);
+                let kind = if extension(name) == "osu"
+                {
+                    BeatmapsetFileKind::Beatmap
+                }
+                else if extension(name) == "osb"
+                {
+                    BeatmapsetFileKind::Storyboard
+                }
+                else if audio.contains(&normalized)
+                {
+                    BeatmapsetFileKind::Audio
+                }
+                else if backgrounds.contains(&normalized)
+                {
+                    BeatmapsetFileKind::Background
+                }
+                else if videos.contains(&normalized)
+                {
+                    BeatmapsetFileKind::Video
+                }
+                else if SOUND_EXTENSIONS.contains(&extension(name).as_str())
+                {
+                    BeatmapsetFileKind::Hitsound
+                }
+                else
+                {
+                    BeatmapsetFileKind::Other
+                };
+
+                BeatmapsetFile {
+                    name: name.clone(),
+                    size: data.len() as u64,
+                    hash: format!("{:x}", md5::compute(data)),
+                    kind,
+                }
+            })
+            .collect_vec();
+
+        let archived = files.iter().map(|file| normalize(&file.name)).collect_vec();
+        let missing_files = audio
+            .into_iter()
+            .chain(backgrounds)
+            .chain(videos)
+            .filter(|name| !name.is_empty() && !archived.contains(name))
+            .collect_vec();
+
+        Ok(ParserBeatmapset {
+            beatmaps,
+            failed_beatmaps,
+            files,
+            missing_files,
+        })
+    }
+
+    pub fn files_of_kind(&self, kind: BeatmapsetFileKind) -> impl Iterator<Item = &BeatmapsetFile>
+    {
+        self.files.iter().filter(move |file| file.kind == kind)
+    }
+}
+
+fn extension(name: &str) -> String
+{
+    name.rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Filenames in .osu files are case insensitive and may use backslashes.
+fn normalize(name: &str) -> String { name.replace('\\', "/").to_lowercase() }
@@ -1,13 +1,24 @@
+pub mod beatmapset;
+pub mod mods;
 pub mod objects;
 pub mod parse;
+pub mod sections;
+pub mod slider;
+pub mod stacking;
+pub mod timing;
+pub mod write;
 
-use rosu_pp::beatmap::TimingPoint;
+use rosu_pp::beatmap::{DifficultyPoint, TimingPoint};
 use serde::{Deserialize, Serialize};
 use tsify::Tsify;
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use self::objects::HitObject;
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+use self::{
+    objects::{HitObject, Pos2},
+    timing::SampleSet,
+};
+use crate::replay::Mode;
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ParserDifficulty
 {
@@ -25,7 +36,7 @@ pub struct ParserDifficulty
     pub stars:               f64,
     pub max_combo:           u32,
 }
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ParserPerformance
 {
@@ -39,44 +50,191 @@ pub struct ParserPerformance
 }
 #[derive(Default, Serialize, Deserialize, Debug, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserTaikoDifficulty
+{
+    pub stamina:    f64,
+    pub rhythm:     f64,
+    pub colour:     f64,
+    pub peak:       f64,
+    pub hit_window: f64,
+    pub stars:      f64,
+    pub max_combo:  u32,
+    pub is_convert: bool,
+}
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserTaikoPerformance
+{
+    pub difficulty:           ParserTaikoDifficulty,
+    pub pp:                   f64,
+    pub pp_acc:               f64,
+    pub pp_difficulty:        f64,
+    pub effective_miss_count: f64,
+}
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserCatchDifficulty
+{
+    pub stars:         f64,
+    pub ar:            f64,
+    pub fruits:        u32,
+    pub droplets:      u32,
+    pub tiny_droplets: u32,
+    pub max_combo:     u32,
+    pub is_convert:    bool,
+}
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserCatchPerformance
+{
+    pub difficulty: ParserCatchDifficulty,
+    pub pp:         f64,
+}
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserManiaDifficulty
+{
+    pub stars:      f64,
+    pub hit_window: f64,
+    pub max_combo:  u32,
+    pub is_convert: bool,
+}
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserManiaPerformance
+{
+    pub difficulty:    ParserManiaDifficulty,
+    pub pp:            f64,
+    pub pp_difficulty: f64,
+}
+/// Difficulty attributes of the mode the beatmap was calculated in.
+#[derive(Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum ParserModeDifficulty
+{
+    Osu(ParserDifficulty),
+    Taiko(ParserTaikoDifficulty),
+    Catch(ParserCatchDifficulty),
+    Mania(ParserManiaDifficulty),
+}
+/// Performance attributes of the mode the beatmap was calculated in.
+#[derive(Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum ParserModePerformance
+{
+    Osu(ParserPerformance),
+    Taiko(ParserTaikoPerformance),
+    Catch(ParserCatchPerformance),
+    Mania(ParserManiaPerformance),
+}
+impl ParserModeDifficulty
+{
+    pub fn as_osu(&self) -> Option<&ParserDifficulty>
+    {
+        match self
+        {
+            ParserModeDifficulty::Osu(difficulty) => Some(difficulty),
+            _ => None,
+        }
+    }
+}
+impl ParserModePerformance
+{
+    pub fn as_osu(&self) -> Option<&ParserPerformance>
+    {
+        match self
+        {
+            ParserModePerformance::Osu(performance) => Some(performance),
+            _ => None,
+        }
+    }
+}
+/// Gradual difficulty and performance attributes.
+///
+/// `difficulty` and `performance` are only set for osu!standard, `mode_difficulty` and
+/// `mode_performance` are set for every mode.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ParserStrains
 {
-    pub difficulty:  Option<Vec<ParserDifficulty>>,
-    pub performance: Option<Vec<ParserPerformance>>,
+    pub difficulty:       Option<Vec<ParserDifficulty>>,
+    pub performance:      Option<Vec<ParserPerformance>>,
+    pub mode_difficulty:  Option<Vec<ParserModeDifficulty>>,
+    pub mode_performance: Option<Vec<ParserModePerformance>>,
 }
+/// Difficulty and performance attributes.
+///
+/// `difficulty` and `performance` are only set for osu!standard, `mode_difficulty` and
+/// `mode_performance` are set for every mode.
 #[derive(Default, Serialize, Deserialize, Debug, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ParserBeatmapAttributes
 {
-    pub difficulty:  Option<ParserDifficulty>,
-    pub performance: Option<ParserPerformance>,
+    pub difficulty:       Option<ParserDifficulty>,
+    pub performance:      Option<ParserPerformance>,
+    pub mode_difficulty:  Option<ParserModeDifficulty>,
+    pub mode_performance: Option<ParserModePerformance>,
 }
 #[derive(Default, Serialize, Deserialize, Debug, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ParserBeatmap
 {
-    pub hash:              String,
-    pub title:             String,
-    pub artist:            String,
-    pub diff_name:         String,
-    pub tags:              Vec<String>,
-    pub combo_colors:      Vec<Color>,
-    pub map_length:        u32,
-    pub drain_time:        u32,
-    pub max_combo:         u32,
-    pub bpm:               Option<f32>,
-    pub circles:           u32,
-    pub sliders:           u32,
-    pub spinners:          u32,
-    pub ar:                f32,
-    pub od:                f32,
-    pub cs:                f32,
-    pub hp:                f32,
-    pub slider_multiplier: f64,
-    pub tick_rate:         f64,
-    pub timing_points:     Option<Vec<ParserTimingPoint>>,
-    pub hit_objects:       Option<Vec<HitObject>>,
-    pub breaks:            Option<Vec<ParserBreak>>,
+    /// Version from the `osu file format v14` header.
+    pub format_version:        u32,
+    pub hash:                  String,
+    pub title:                 String,
+    pub artist:                String,
+    pub diff_name:             String,
+    pub tags:                  Vec<String>,
+    pub title_unicode:         String,
+    pub artist_unicode:        String,
+    pub creator:               String,
+    pub source:                String,
+    pub beatmap_id:            Option<i32>,
+    pub beatmapset_id:         Option<i32>,
+    pub audio_filename:        String,
+    pub audio_lead_in:         i32,
+    pub preview_time:          i32,
+    pub stack_leniency:        f32,
+    pub mode:                  Mode,
+    pub countdown:             u8,
+    pub sample_set:            SampleSet,
+    pub letterbox_in_breaks:   bool,
+    pub epilepsy_warning:      bool,
+    pub widescreen_storyboard: bool,
+    pub background:            Option<String>,
+    /// Offset of the background from the centre of the screen in osu!pixels.
+    pub background_offset:     Pos2,
+    pub video:                 Option<String>,
+    /// Time in ms the video starts playing at.
+    pub video_start_time:      i32,
+    /// Offset of the video from the centre of the screen in osu!pixels.
+    pub video_offset:          Pos2,
+    pub has_storyboard:        bool,
+    /// Raw lines of the `[Events]` section other than the background, video and breaks.
+    pub storyboard:            Vec<String>,
+    pub combo_colors:          Vec<Color>,
+    pub slider_track_override: Option<Color>,
+    pub slider_border:         Option<Color>,
+    /// Raw `Key: Value` lines of the `[Editor]` section.
+    pub editor:                Vec<String>,
+    pub map_length:            u32,
+    pub drain_time:            u32,
+    pub max_combo:             u32,
+    pub bpm:                   Option<f32>,
+    pub circles:               u32,
+    pub sliders:               u32,
+    pub spinners:              u32,
+    pub ar:                    f32,
+    pub od:                    f32,
+    pub cs:                    f32,
+    pub hp:                    f32,
+    pub slider_multiplier:     f64,
+    pub tick_rate:             f64,
+    /// Uninherited and inherited timing points sorted by time.
+    pub timing_points:         Option<Vec<ParserTimingPoint>>,
+    pub hit_objects:           Option<Vec<HitObject>>,
+    pub breaks:                Option<Vec<ParserBreak>>,
 }
 
 #[derive(Default, Serialize, Deserialize, Debug, Tsify)]
@@ -98,20 +256,64 @@ impl From<&rosu_pp::beatmap::Break> for ParserBreak
     }
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+/// Timing point line of the `[TimingPoints]` section, both uninherited (red) and inherited
+/// (green) lines.
+#[derive(Serialize, Deserialize, Debug, Tsify, Clone)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct ParserTimingPoint
 {
-    pub time:        f64,
-    pub beat_length: f64,
+    pub time:               f64,
+    /// Raw beat length, negative inverse slider velocity percentage for inherited points.
+    pub beat_length:        f64,
+    pub meter:              u32,
+    pub sample_set:         SampleSet,
+    pub sample_index:       u32,
+    pub volume:             u32,
+    pub uninherited:        bool,
+    pub kiai:               bool,
+    pub omit_first_barline: bool,
+    /// Slider velocity multiplier, always 1 for uninherited points.
+    pub slider_velocity:    f64,
+}
+impl Default for ParserTimingPoint
+{
+    fn default() -> Self
+    {
+        Self {
+            time:               0.0,
+            beat_length:        1000.0,
+            meter:              4,
+            sample_set:         SampleSet::Default,
+            sample_index:       0,
+            volume:             100,
+            uninherited:        true,
+            kiai:               false,
+            omit_first_barline: false,
+            slider_velocity:    1.0,
+        }
+    }
 }
 impl From<&TimingPoint> for ParserTimingPoint
 {
     fn from(value: &TimingPoint) -> Self
     {
         Self {
-            time:        value.time,
+            time: value.time,
             beat_length: value.beat_len,
+            ..Default::default()
+        }
+    }
+}
+impl From<&DifficultyPoint> for ParserTimingPoint
+{
+    fn from(value: &DifficultyPoint) -> Self
+    {
+        Self {
+            time: value.time,
+            beat_length: -100.0 / value.slider_vel,
+            uninherited: false,
+            slider_velocity: value.slider_vel,
+            ..Default::default()
         }
     }
 }
@@ -138,6 +340,20 @@ pub struct Color
     pub blue: u8,
 }
 
+impl Color
+{
+    /// Parses a `red,green,blue` value of the `[Colours]` section.
+    pub fn parse(value: &str) -> Option<Self>
+    {
+        let mut channels = value.split(',').map(|x| x.trim().parse::<u8>());
+        Some(Self {
+            red:   channels.next()?.ok()?,
+            green: channels.next()?.ok()?,
+            blue:  channels.next()?.ok()?,
+        })
+    }
+}
+
 impl From<libosu::prelude::Color> for Color
 {
     fn from(value: libosu::prelude::Color) -> Self
@@ -0,0 +1,288 @@
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    objects::{HitWindows, Pos2},
+    ParserBeatmap,
+};
+use crate::replay::{Mode, Mods, ParserError, ParserResult};
+
+/// Rate changed .osu file.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct RateChangedBeatmap
+{
+    pub osu:         String,
+    /// MD5 hash of the new .osu file.
+    pub hash:        String,
+    /// AR the rate results in if it had to be clamped between 0 and 10, the beatmap then plays
+    /// differently than the rate change.
+    pub uncapped_ar: Option<f32>,
+    /// OD the rate results in if it had to be clamped between 0 and 10.
+    pub uncapped_od: Option<f32>,
+}
+
+/// Scales a difficulty setting for HR or EZ.
+pub fn scale_difficulty(value: f32, mods: Mods, hard_rock_multiplier: f32) -> f32
+{
+    if mods.contains(Mods::HardRock)
+    {
+        (value * hard_rock_multiplier).min(10.0)
+    }
+    else if mods.contains(Mods::Easy)
+    {
+        value * 0.5
+    }
+    else
+    {
+        value
+    }
+}
+
+/// Converts an approach rate to its preempt time in ms.
+pub fn ar_to_ms(ar: f32) -> f32
+{
+    if ar < 5.0
+    {
+        1200.0 + 600.0 * (5.0 - ar) / 5.0
+    }
+    else
+    {
+        1200.0 - 750.0 * (ar - 5.0) / 5.0
+    }
+}
+
+pub fn ms_to_ar(ms: f32) -> f32
+{
+    if ms > 1200.0
+    {
+        5.0 - (ms - 1200.0) / 120.0
+    }
+    else
+    {
+        5.0 + (1200.0 - ms) / 150.0
+    }
+}
+
+/// Converts an overall difficulty to its 300 hit window in ms, see [`HitWindows::with_od`].
+pub fn od_to_ms(od: f32) -> f32 { HitWindows::Hit300.with_od(od) }
+
+pub fn ms_to_od(ms: f32) -> f32 { (f32::from(HitWindows::Hit300) - ms) / 6.0 }
+
+impl ParserBeatmap
+{
+    pub fn parse_with_mods<R: Read + Clone + std::convert::AsRef<[u8]>>(
+        beatmap: &mut R, mods: u32,
+    ) -> ParserResult<Self>
+    {
+        let mods = Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?;
+        Ok(ParserBeatmap::parse(beatmap)?.with_mods(mods))
+    }
+
+    /// Applies the effect of mods to the difficulty settings, BPM and lengths.
+    ///
+    /// AR and OD are converted through their ms values so they reflect the DT/HT clock rate.
+    /// Hit objects, timing points and breaks are left untouched.
+    pub fn with_mods(self, mods: Mods) -> Self
+    {
+        Self {
+            ar: scale_difficulty(self.ar, mods, 1.4),
+            od: scale_difficulty(self.od, mods, 1.4),
+            cs: scale_difficulty(self.cs, mods, 1.3),
+            hp: scale_difficulty(self.hp, mods, 1.4),
+            ..self
+        }
+        .with_clock_rate(mods.clock_rate())
+    }
+
+    /// Adjusts AR, OD, BPM and lengths to a clock rate without touching any times.
+    fn with_clock_rate(self, clock_rate: f64) -> Self
+    {
+        let (ar, od) = self.clock_rate_difficulty(clock_rate);
+        let clock_rate = clock_rate as f32;
+        Self {
+            ar,
+            od,
+            bpm: self.bpm.map(|bpm| bpm * clock_rate),
+            map_length: (self.map_length as f32 / clock_rate) as u32,
+            drain_time: (self.drain_time as f32 / clock_rate) as u32,
+            ..self
+        }
+    }
+
+    /// AR and OD with their ms values scaled by a clock rate.
+    fn clock_rate_difficulty(&self, clock_rate: f64) -> (f32, f32)
+    {
+        let clock_rate = clock_rate as f32;
+        (
+            ms_to_ar(ar_to_ms(self.ar) / clock_rate),
+            ms_to_od(od_to_ms(self.od) / clock_rate),
+        )
+    }
+
+    /// Flips hit objects like HR and Mirror do, HR only flips osu!standard beatmaps.
+    ///
+    /// Replay frames are recorded in beatmap time against the flipped objects, so this is the
+    /// beatmap to compare them with.
+    pub fn flipped(mut self, mods: Mods) -> Self
+    {
+        let (flip_vertically, flip_horizontally) = self.flips(mods);
+        for object in self.hit_objects.iter_mut().flatten()
+        {
+            if flip_vertically
+            {
+                object.flip_vertically();
+            }
+            if flip_horizontally
+            {
+                object.flip_horizontally();
+            }
+        }
+        self
+    }
+
+    /// Playfield position after the flips of [`ParserBeatmap::flipped`].
+    pub fn flip_position(&self, mut pos: Pos2, mods: Mods) -> Pos2
+    {
+        let (flip_vertically, flip_horizontally) = self.flips(mods);
+        if flip_vertically
+        {
+            pos = pos.flip_vertically();
+        }
+        if flip_horizontally
+        {
+            pos = pos.flip_horizontally();
+        }
+        pos
+    }
+
+    /// Whether the hit objects are flipped vertically and horizontally.
+    fn flips(&self, mods: Mods) -> (bool, bool)
+    {
+        (
+            mods.contains(Mods::HardRock) && self.mode == Mode::Osu,
+            mods.contains(Mods::Mirror),
+        )
+    }
+
+    /// Applies every effect of mods, flipping hit objects and scaling all times by the clock rate
+    /// on top of [`ParserBeatmap::with_mods`].
+    pub fn transformed(self, mods: Mods) -> Self
+    {
+        let mut map = self.flipped(mods).with_mods(mods);
+        map.scale_times(mods.clock_rate());
+        map
+    }
+
+    /// Changes the rate of the beatmap like DT/HT do, but for any rate.
+    ///
+    /// AR and OD are clamped between 0 and 10 since the .osu format does not support other values,
+    /// which fast and slow rates can result in, and the rate is appended to the difficulty name.
+    /// The beatmap ID is set to 0, as the result is no longer the submitted beatmap. Storyboard lines of the .osu and the video are scaled too,
+    /// a separate .osb storyboard is not.
+    pub fn with_rate(self, rate: f64) -> Self
+    {
+        let diff_name = format!("{} ({}x)", self.diff_name, rate);
+        let mut map = self.with_clock_rate(rate);
+        map.scale_times(rate);
+        Self {
+            ar: map.ar.clamp(0.0, 10.0),
+            od: map.od.clamp(0.0, 10.0),
+            diff_name,
+            beatmap_id: Some(0),
+            ..map
+        }
+    }
+
+    /// Parses a beatmap and generates the .osu file of it at another rate.
+    ///
+    /// Only the beatmap is changed, the audio file has to be rate changed separately.
+    pub fn generate_rate_changed<R: Read + Clone + std::convert::AsRef<[u8]>>(
+        beatmap: &mut R, rate: f64,
+    ) -> ParserResult<RateChangedBeatmap>
+    {
+        if !rate.is_finite() || rate <= 0.0
+        {
+            return Err(ParserError::InvalidRate(rate));
+        }
+        let parsed = ParserBeatmap::parse(beatmap)?;
+        let (ar, od) = parsed.clock_rate_difficulty(rate);
+        let osu = parsed.with_rate(rate).to_osu_string()?;
+        Ok(RateChangedBeatmap {
+            hash: format!("{:x}", md5::compute(&osu)),
+            osu,
+            uncapped_ar: (!(0.0..=10.0).contains(&ar)).then_some(ar),
+            uncapped_od: (!(0.0..=10.0).contains(&od)).then_some(od),
+        })
+    }
+
+    /// Scales every time of the beatmap by a clock rate.
+    fn scale_times(&mut self, clock_rate: f64)
+    {
+        if clock_rate == 1.0
+        {
+            return;
+        }
+
+        let scale = |time: f64| time / clock_rate;
+        for object in self.hit_objects.iter_mut().flatten()
+        {
+            object.scale_time(clock_rate);
+        }
+        for point in self.timing_points.iter_mut().flatten()
+        {
+            point.time = scale(point.time);
+            // Inherited beat lengths are slider velocity multipliers
+            if point.uninherited
+            {
+                point.beat_length = scale(point.beat_length);
+            }
+        }
+        for break_period in self.breaks.iter_mut().flatten()
+        {
+            break_period.start_time = scale(break_period.start_time as f64).round() as u32;
+            break_period.end_time = scale(break_period.end_time as f64).round() as u32;
+        }
+        if self.preview_time >= 0
+        {
+            self.preview_time = scale(self.preview_time as f64).round() as i32;
+        }
+        self.video_start_time = scale(self.video_start_time as f64).round() as i32;
+        for line in &mut self.storyboard
+        {
+            *line = scale_event_line(line, clock_rate);
+        }
+    }
+}
+
+/// Scales the times of a raw `[Events]` line, which are whole ms like hit object times.
+///
+/// Commands inside loops are relative to the loop start, so they are scaled the same way.
+fn scale_event_line(line: &str, clock_rate: f64) -> String
+{
+    let body = line.trim_start_matches([' ', '_']);
+    let indent = &line[..line.len() - body.len()];
+    let mut fields = body.split(',').map(str::to_string).collect::<Vec<_>>();
+    let time_fields: &[usize] = match (indent.is_empty(), fields[0].as_str())
+    {
+        // Loop start, other commands have an easing or trigger before the start and end time
+        (false, "L") => &[1],
+        (false, _) => &[2, 3],
+        // Sample and background colour times, and the animation frame delay
+        (true, "Sample" | "5" | "3") => &[1],
+        (true, "Animation" | "6") => &[7],
+        (true, _) => &[],
+    };
+    for index in time_fields
+    {
+        if let Some(time) = fields
+            .get(*index)
+            .and_then(|field| field.trim().parse::<f64>().ok())
+        {
+            fields[*index] = (time / clock_rate).round().to_string();
+        }
+    }
+    format!("{}{}", indent, fields.join(","))
+}
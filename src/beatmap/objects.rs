@@ -1,7 +1,13 @@
+use std::ops::{Add, Mul, Sub};
+
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use tsify::Tsify;
 
+pub const PLAYFIELD_WIDTH: f32 = 512.0;
+pub const PLAYFIELD_HEIGHT: f32 = 384.0;
+
+#[derive(Clone, Copy)]
 pub enum HitWindows
 {
     Hit50  = 199,
@@ -16,22 +22,198 @@ impl From<HitWindows> for f32
 
 impl HitWindows
 {
-    pub fn with_od(self, od: f32) -> f32 { f32::from(self) - (od * 10.0) }
+    pub fn with_od(self, od: f32) -> f32
+    {
+        let od_multiplier = match self
+        {
+            HitWindows::Hit50 => 10.0,
+            HitWindows::Hit100 => 8.0,
+            HitWindows::Hit300 => 6.0,
+        };
+        f32::from(self) - (od * od_multiplier)
+    }
 }
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct Pos2
 {
     pub x: f32,
     pub y: f32,
 }
+
+impl Pos2
+{
+    pub fn length(&self) -> f32 { self.length_squared().sqrt() }
+
+    pub fn length_squared(&self) -> f32 { self.x * self.x + self.y * self.y }
+
+    pub fn distance(&self, other: Pos2) -> f32 { (*self - other).length() }
+
+    /// Mirrors a playfield position vertically, as done by HR.
+    pub fn flip_vertically(self) -> Pos2
+    {
+        Pos2 {
+            x: self.x,
+            y: PLAYFIELD_HEIGHT - self.y,
+        }
+    }
+
+    /// Mirrors a playfield position horizontally, as done by Mirror.
+    pub fn flip_horizontally(self) -> Pos2
+    {
+        Pos2 {
+            x: PLAYFIELD_WIDTH - self.x,
+            y: self.y,
+        }
+    }
+
+    pub fn normalize(self) -> Pos2
+    {
+        let length = self.length();
+        if length == 0.0
+        {
+            self
+        }
+        else
+        {
+            self * (1.0 / length)
+        }
+    }
+}
+
+impl Add for Pos2
+{
+    type Output = Pos2;
+
+    fn add(self, rhs: Pos2) -> Pos2
+    {
+        Pos2 {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl Sub for Pos2
+{
+    type Output = Pos2;
+
+    fn sub(self, rhs: Pos2) -> Pos2
+    {
+        Pos2 {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl Mul<f32> for Pos2
+{
+    type Output = Pos2;
+
+    fn mul(self, rhs: f32) -> Pos2
+    {
+        Pos2 {
+            x: self.x * rhs,
+            y: self.y * rhs,
+        }
+    }
+}
 #[derive(Default, Serialize, Deserialize, Debug, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct HitObject
 {
-    pub pos:        Pos2,
-    pub start_time: f64,
-    pub kind:       HitObjectKind,
+    pub pos:          Pos2,
+    pub start_time:   f64,
+    pub kind:         HitObjectKind,
+    pub new_combo:    bool,
+    /// Number of combo colours skipped by a new combo.
+    pub combo_offset: u8,
+    pub hit_sound:    u8,
+    /// Raw `normalSet:additionSet:index:volume:filename` sample, empty if the line has none.
+    pub hit_sample:   String,
+    /// Raw `normalSet:additionSet` samples of every slider edge.
+    pub edge_sets:    Vec<String>,
+}
+
+const TYPE_NEW_COMBO: u8 = 1 << 2;
+const TYPE_COMBO_OFFSET_SHIFT: u8 = 4;
+
+impl HitObject
+{
+    /// Reads the combo and hitsound fields of a `[HitObjects]` line, which rosu-pp skips.
+    ///
+    /// Returns `false` without changes if the line is not the one of this object.
+    pub fn extend_from_line(&mut self, line: &str) -> bool
+    {
+        let fields = line.split(',').map(str::trim).collect_vec();
+        let time = fields.get(2).and_then(|x| x.parse::<f64>().ok());
+        if fields.len() < 5 || !time.is_some_and(|time| (time - self.start_time).abs() < 1.0)
+        {
+            return false;
+        }
+
+        let kind = fields[3].parse::<u8>().unwrap_or_default();
+        self.new_combo = kind & TYPE_NEW_COMBO != 0;
+        self.combo_offset = (kind >> TYPE_COMBO_OFFSET_SHIFT) & 0b111;
+        self.hit_sound = fields[4].parse().unwrap_or_default();
+        let hit_sample = match &self.kind
+        {
+            HitObjectKind::Circle => fields.get(5).copied(),
+            HitObjectKind::Slider { .. } =>
+            {
+                self.edge_sets = fields
+                    .get(9)
+                    .map(|sets| sets.split('|').map(str::to_string).collect())
+                    .unwrap_or_default();
+                fields.get(10).copied()
+            }
+            HitObjectKind::Spinner { .. } => fields.get(6).copied(),
+            // Hold notes separate the end time and the sample with a colon
+            HitObjectKind::Hold { .. } => fields
+                .get(5)
+                .and_then(|x| x.split_once(':'))
+                .map(|(_, sample)| sample),
+        };
+        self.hit_sample = hit_sample.unwrap_or_default().to_string();
+        true
+    }
+
+    /// Mirrors the object and its slider path vertically.
+    pub fn flip_vertically(&mut self)
+    {
+        self.pos = self.pos.flip_vertically();
+        // Control points are relative to the object position
+        for point in self.kind.control_points_mut()
+        {
+            point.pos.y = -point.pos.y;
+        }
+    }
+
+    /// Mirrors the object and its slider path horizontally.
+    pub fn flip_horizontally(&mut self)
+    {
+        self.pos = self.pos.flip_horizontally();
+        for point in self.kind.control_points_mut()
+        {
+            point.pos.x = -point.pos.x;
+        }
+    }
+
+    /// Scales the times of the object for a clock rate.
+    pub fn scale_time(&mut self, clock_rate: f64)
+    {
+        self.start_time /= clock_rate;
+        match &mut self.kind
+        {
+            HitObjectKind::Spinner { end_time } | HitObjectKind::Hold { end_time } =>
+            {
+                *end_time /= clock_rate
+            }
+            HitObjectKind::Circle | HitObjectKind::Slider { .. } =>
+            {}
+        }
+    }
 }
 #[derive(Default, Serialize, Deserialize, Debug, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
@@ -56,7 +238,19 @@ pub enum HitObjectKind
     },
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+impl HitObjectKind
+{
+    fn control_points_mut(&mut self) -> &mut [PathControlPoint]
+    {
+        match self
+        {
+            HitObjectKind::Slider { control_points, .. } => control_points,
+            _ => &mut [],
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct PathControlPoint
 {
@@ -64,7 +258,7 @@ pub struct PathControlPoint
     pub kind: Option<PathType>,
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub enum PathType
 {
@@ -90,9 +284,10 @@ impl From<rosu_pp::parse::HitObject> for HitObject
     fn from(value: rosu_pp::parse::HitObject) -> Self
     {
         Self {
-            pos:        value.pos.into(),
+            pos: value.pos.into(),
             start_time: value.start_time,
-            kind:       value.kind.into(),
+            kind: value.kind.into(),
+            ..Default::default()
         }
     }
 }
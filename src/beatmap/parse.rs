@@ -1,23 +1,29 @@
-use std::io::Read;
+use std::{borrow::Cow, convert::TryFrom, io::Read};
 
 use itertools::Itertools;
 use libosu::prelude::Beatmap as libosuBeatmap;
 use rosu_pp::{
-    osu::{
-        OsuDifficultyAttributes, OsuGradualDifficultyAttributes, OsuGradualPerformanceAttributes,
-        OsuPerformanceAttributes, OsuScoreState,
-    },
-    Beatmap, OsuPP,
+    catch::{CatchDifficultyAttributes, CatchPerformanceAttributes},
+    mania::{ManiaDifficultyAttributes, ManiaPerformanceAttributes},
+    osu::{OsuDifficultyAttributes, OsuPerformanceAttributes, OsuScoreState},
+    taiko::{TaikoDifficultyAttributes, TaikoPerformanceAttributes},
+    AnyPP, Beatmap, DifficultyAttributes, GameMode, GradualDifficultyAttributes,
+    GradualPerformanceAttributes, OsuPP, PerformanceAttributes, ScoreState,
 };
 
 use crate::{
-    replay::{ParserError, ParserResult},
+    replay::{Mode, ParserError, ParserResult},
     ParserScore,
 };
 
+use super::timing::SampleSet;
 use super::{
-    objects::HitObject, Color, ParserBeatmap, ParserBeatmapAttributes, ParserBreak,
-    ParserDifficulty, ParserPerformance, ParserScoreState, ParserStrains, ParserTimingPoint,
+    objects::{HitObject, Pos2},
+    sections::OsuSections,
+    Color, ParserBeatmap, ParserBeatmapAttributes, ParserBreak, ParserCatchDifficulty,
+    ParserCatchPerformance, ParserDifficulty, ParserManiaDifficulty, ParserManiaPerformance,
+    ParserModeDifficulty, ParserModePerformance, ParserPerformance, ParserScoreState,
+    ParserStrains, ParserTaikoDifficulty, ParserTaikoPerformance, ParserTimingPoint,
 };
 impl ParserBeatmap
 {
@@ -27,7 +33,10 @@ impl ParserBeatmap
         let rosu_map = Beatmap::parse(beatmap.as_ref())?;
 
         let mut map = ParserBeatmap::from(rosu_map.clone())
-            .extend_from_libosu(&libosuBeatmap::parse(beatmap.as_ref())?);
+            .extend_from_libosu(&libosuBeatmap::parse(beatmap.as_ref())?)
+            .extend_from_sections(&OsuSections::parse(&String::from_utf8_lossy(
+                beatmap.as_ref(),
+            )));
 
         map.max_combo = OsuPP::new(&rosu_map).calculate().difficulty.max_combo as u32;
         map.hash = String::from(format!("{:x}", md5::compute(beatmap)));
@@ -38,27 +47,44 @@ impl ParserBeatmap
             .as_ref()
             .ok_or(ParserError::HitobjectsMissing)?
             .last()
-            .unwrap()
-            .start_time as u32;
+            .map_or(0, |object| object.start_time as u32);
         map.drain_time = map.get_drain_time();
 
         Ok(map)
     }
 
+    /// Beatmap in the requested mode, osu!standard beatmaps are converted.
+    fn converted(map: &Beatmap, mode: Option<u8>) -> ParserResult<Cow<'_, Beatmap>>
+    {
+        let mode = match mode
+        {
+            Some(mode) => Mode::try_from(mode)?,
+            None => return Ok(Cow::Borrowed(map)),
+        };
+        let map_mode = Mode::from(map.mode);
+        if map_mode != mode && map_mode != Mode::Osu
+        {
+            return Err(ParserError::UnsupportedConversion(map_mode, mode));
+        }
+        Ok(map.convert_mode(mode.into()))
+    }
+
     pub fn parse_beatmap_strains<R: Read + Clone + std::convert::AsRef<[u8]>>(
         beatmap: &mut R, score_states: Option<Vec<ParserScoreState>>, mods: Option<u32>,
+        mode: Option<u8>,
     ) -> ParserResult<ParserStrains>
     {
-        let map = Beatmap::parse(&mut beatmap.as_ref())?;
+        let rosu_map = Beatmap::parse(&mut beatmap.as_ref())?;
+        let map = ParserBeatmap::converted(&rosu_map, mode)?;
 
-        let gradual_strains = OsuGradualDifficultyAttributes::new(&map, mods.unwrap_or(0))
-            .map(|x| ParserDifficulty::from(x))
+        let gradual_strains = GradualDifficultyAttributes::new(&map, mods.unwrap_or(0))
+            .map(ParserModeDifficulty::from)
             .collect_vec();
 
         let gradual_perf = if let Some(states) = score_states
         {
-            let mut perf = OsuGradualPerformanceAttributes::new(&map, mods.unwrap_or(0));
-            let mut perf_results: Vec<ParserPerformance> = Vec::new();
+            let mut perf = GradualPerformanceAttributes::new(&map, mods.unwrap_or(0));
+            let mut perf_results: Vec<ParserModePerformance> = Vec::new();
             for state in states
             {
                 let res = perf.process_next_object(state.into());
@@ -76,61 +102,83 @@ impl ParserBeatmap
         };
 
         Ok(ParserStrains {
-            difficulty:  Some(gradual_strains),
-            performance: gradual_perf,
+            difficulty:       gradual_strains
+                .iter()
+                .map(|x| x.as_osu().cloned())
+                .collect(),
+            performance:      gradual_perf
+                .as_ref()
+                .and_then(|perf| perf.iter().map(|x| x.as_osu().cloned()).collect()),
+            mode_difficulty:  Some(gradual_strains),
+            mode_performance: gradual_perf,
         })
     }
 
     pub fn parse_beatmap_attributes<R: Read + Clone + std::convert::AsRef<[u8]>>(
-        score: Option<ParserScore>, beatmap: &mut R,
+        score: Option<ParserScore>, beatmap: &mut R, mode: Option<u8>,
     ) -> ParserResult<ParserBeatmapAttributes>
     {
         let rosu_map = Beatmap::parse(&mut beatmap.as_ref())?;
+        let map = ParserBeatmap::converted(&rosu_map, mode)?;
 
-        let perf_result = match score
+        let mut calc = AnyPP::new(&map);
+        if let Some(score) = score
         {
-            Some(score) =>
+            if let Some(mods) = score.mods
             {
-                let mut osupp = OsuPP::new(&rosu_map);
-                if let Some(mods) = score.mods
+                calc = calc.mods(mods);
+            }
+            if let Some(combo) = score.combo
+            {
+                calc = calc.combo(combo);
+            }
+            if let Some(judgements) = score.judgements
+            {
+                if let Some(count_geki) = judgements.count_geki
+                {
+                    calc = calc.n_geki(count_geki as _);
+                }
+                if let Some(count_katu) = judgements.count_katu
                 {
-                    osupp = osupp.mods(mods);
+                    calc = calc.n_katu(count_katu as _);
                 }
-                if let Some(combo) = score.combo
+                if let Some(count_300) = judgements.count_300
                 {
-                    osupp = osupp.combo(combo);
+                    calc = calc.n300(count_300 as _);
                 }
-                if let Some(judgements) = score.judgements
+                if let Some(count_100) = judgements.count_100
                 {
-                    if let Some(count_300) = judgements.count_300
-                    {
-                        osupp = osupp.n300(count_300 as _);
-                    }
-                    if let Some(count_100) = judgements.count_100
-                    {
-                        osupp = osupp.n100(count_100 as _);
-                    }
-                    if let Some(count_50) = judgements.count_50
-                    {
-                        osupp = osupp.n50(count_50 as _);
-                    }
-                    if let Some(miss) = judgements.miss
-                    {
-                        osupp = osupp.n_misses(miss as _);
-                    }
+                    calc = calc.n100(count_100 as _);
                 }
-                if let Some(accuracy) = score.accuracy
+                if let Some(count_50) = judgements.count_50
                 {
-                    osupp = osupp.accuracy(accuracy)
+                    calc = calc.n50(count_50 as _);
                 }
-                osupp.calculate()
+                if let Some(miss) = judgements.miss
+                {
+                    calc = calc.n_misses(miss as _);
+                }
+            }
+            if let Some(accuracy) = score.accuracy
+            {
+                calc = calc.accuracy(accuracy)
             }
-            None => OsuPP::new(&rosu_map).calculate(),
+        }
+        let perf_result = calc.calculate();
+
+        let (difficulty, performance) = match &perf_result
+        {
+            PerformanceAttributes::Osu(perf) => (
+                Some(perf.difficulty.clone().into()),
+                Some(perf.clone().into()),
+            ),
+            _ => (None, None),
         };
-        let diff_result = perf_result.clone().difficulty;
         Ok(ParserBeatmapAttributes {
-            difficulty:  Some(diff_result.into()),
-            performance: Some(perf_result.into()),
+            difficulty,
+            performance,
+            mode_difficulty: Some(perf_result.clone().into()),
+            mode_performance: Some(perf_result.into()),
         })
     }
 
@@ -146,13 +194,7 @@ impl ParserBeatmap
             hp: value.hp,
             slider_multiplier: value.slider_mult,
             tick_rate: value.tick_rate,
-            timing_points: Some(
-                value
-                    .timing_points
-                    .iter()
-                    .map(ParserTimingPoint::from)
-                    .collect_vec(),
-            ),
+            timing_points: Some(timing_points_from_rosu(value)),
             breaks: Some(value.breaks.iter().map(ParserBreak::from).collect_vec()),
             ..self
         }
@@ -160,15 +202,126 @@ impl ParserBeatmap
     pub fn extend_from_libosu(self, value: &libosuBeatmap) -> Self
     {
         Self {
+            format_version: value.version,
             title: value.title.clone(),
+            title_unicode: value.title_unicode.clone(),
             artist: value.artist.clone(),
+            artist_unicode: value.artist_unicode.clone(),
+            creator: value.creator.clone(),
+            source: value.source.clone(),
             tags: value.tags.clone(),
             diff_name: value.difficulty_name.clone(),
+            beatmap_id: Some(value.beatmap_id).filter(|id| *id > 0),
+            beatmapset_id: Some(value.beatmap_set_id).filter(|id| *id > 0),
+            audio_filename: value.audio_filename.clone(),
+            audio_lead_in: value.audio_leadin as i32,
+            preview_time: value.preview_time as i32,
+            stack_leniency: value.stack_leniency as f32,
+            mode: Mode::try_from(value.mode as u8).unwrap_or_default(),
+            countdown: value.countdown as u8,
+            sample_set: SampleSet::from(value.sample_set as u32),
+            letterbox_in_breaks: value.letterbox_in_breaks,
+            widescreen_storyboard: value.widescreen_storyboard,
             combo_colors: value.colors.iter().map(|x| Color::from(*x)).collect_vec(),
 
             ..self
         }
     }
+    /// Fills in what neither rosu-pp nor libosu keep, the raw `[Events]` and `[Editor]` lines,
+    /// the epilepsy warning, the slider colours, the hit sample settings of the objects and the
+    /// inherited timing points.
+    pub fn extend_from_sections(mut self, value: &OsuSections) -> Self
+    {
+        let mut background = None;
+        let mut background_offset = Pos2::default();
+        let mut video = None;
+        let mut video_start_time = 0;
+        let mut video_offset = Pos2::default();
+        let mut has_storyboard = false;
+        let mut storyboard = Vec::new();
+        for line in value.lines("Events")
+        {
+            // Indented lines are storyboard commands
+            if line.starts_with(' ') || line.starts_with('_')
+            {
+                has_storyboard = true;
+                storyboard.push(line.clone());
+                continue;
+            }
+            let fields = line.split(',').map(str::trim).collect_vec();
+            let filename = fields.get(2).map(|x| x.trim_matches('"').to_string());
+            let field = |index: usize| fields.get(index).and_then(|x| x.parse::<f32>().ok());
+            let offset = Pos2 {
+                x: field(3).unwrap_or_default(),
+                y: field(4).unwrap_or_default(),
+            };
+            match fields[0]
+            {
+                "0" =>
+                {
+                    background = filename;
+                    background_offset = offset;
+                }
+                "1" | "Video" =>
+                {
+                    video = filename;
+                    video_start_time = field(1).unwrap_or_default() as i32;
+                    video_offset = offset;
+                }
+                "2" | "Break" =>
+                {}
+                "Sprite" | "Animation" | "Sample" | "4" | "5" | "6" =>
+                {
+                    has_storyboard = true;
+                    storyboard.push(line.clone());
+                }
+                _ => storyboard.push(line.clone()),
+            }
+        }
+
+        let timing_points = ParserBeatmap::parse_timing_points(value);
+
+        // rosu-pp skips invalid lines, so the lines are matched to the objects by their time
+        let mut lines = value.lines("HitObjects").iter();
+        for object in self.hit_objects.iter_mut().flatten()
+        {
+            for line in lines.by_ref()
+            {
+                if object.extend_from_line(line)
+                {
+                    break;
+                }
+            }
+        }
+
+        Self {
+            epilepsy_warning: value.parse_value::<u8>("General", "EpilepsyWarning") == Some(1),
+            background,
+            background_offset,
+            video,
+            video_start_time,
+            video_offset,
+            slider_track_override: value
+                .value("Colours", "SliderTrackOverride")
+                .and_then(Color::parse),
+            slider_border: value
+                .value("Colours", "SliderBorder")
+                .and_then(Color::parse),
+            editor: value.lines("Editor").to_vec(),
+            has_storyboard,
+            storyboard,
+            // The sections are empty for beatmaps which were not parsed from text
+            timing_points: if timing_points.is_empty()
+            {
+                self.timing_points
+            }
+            else
+            {
+                Some(timing_points)
+            },
+            ..self
+        }
+    }
     pub fn get_drain_time(&self) -> u32
     {
         if let Some(breaks) = &self.breaks
@@ -185,13 +338,19 @@ impl ParserBeatmap
     }
     pub fn get_bpm(&self) -> Option<f32>
     {
-        match &self.timing_points
+        let timing_points = self
+            .timing_points
+            .iter()
+            .flatten()
+            .filter(|point| point.uninherited)
+            .collect_vec();
+        match timing_points.first()
         {
-            Some(timing_points) =>
+            Some(first) =>
             {
                 if timing_points.len() == 1 || self.hit_objects.is_none()
                 {
-                    Some(60000.0 / timing_points.first().unwrap().beat_length as f32)
+                    Some(60000.0 / first.beat_length as f32)
                 }
                 else
                 {
@@ -245,13 +404,7 @@ impl From<rosu_pp::Beatmap> for ParserBeatmap
             hp: value.hp,
             slider_multiplier: value.slider_mult,
             tick_rate: value.tick_rate,
-            timing_points: Some(
-                value
-                    .timing_points
-                    .iter()
-                    .map(ParserTimingPoint::from)
-                    .collect_vec(),
-            ),
+            timing_points: Some(timing_points_from_rosu(&value)),
             hit_objects: Some(
                 value
                     .hit_objects
@@ -264,6 +417,20 @@ impl From<rosu_pp::Beatmap> for ParserBeatmap
         }
     }
 }
+/// Uninherited timing points and inherited slider velocity points of rosu-pp, without their
+/// sample and effect settings.
+fn timing_points_from_rosu(value: &Beatmap) -> Vec<ParserTimingPoint>
+{
+    // Stable sort, uninherited points come first at the same time
+    value
+        .timing_points
+        .iter()
+        .map(ParserTimingPoint::from)
+        .chain(value.difficulty_points.iter().map(ParserTimingPoint::from))
+        .sorted_by(|a, b| a.time.total_cmp(&b.time))
+        .collect()
+}
+
 impl From<OsuDifficultyAttributes> for ParserDifficulty
 {
     fn from(value: OsuDifficultyAttributes) -> Self
@@ -302,25 +469,30 @@ impl From<OsuPerformanceAttributes> for ParserPerformance
 }
 impl From<libosuBeatmap> for ParserBeatmap
 {
-    fn from(value: libosuBeatmap) -> Self
+    fn from(value: libosuBeatmap) -> Self { ParserBeatmap::default().extend_from_libosu(&value) }
+}
+
+impl From<ParserScoreState> for OsuScoreState
+{
+    fn from(value: ParserScoreState) -> Self
     {
         Self {
-            title: value.title,
-            artist: value.artist,
-            tags: value.tags,
-            diff_name: value.difficulty_name,
-            combo_colors: value.colors.iter().map(|x| Color::from(*x)).collect_vec(),
-            ..Default::default()
+            max_combo: value.max_combo as _,
+            n300:      value.n300 as _,
+            n100:      value.n100 as _,
+            n50:       value.n50 as _,
+            n_misses:  value.n_misses as _,
         }
     }
 }
-
-impl From<ParserScoreState> for OsuScoreState
+impl From<ParserScoreState> for ScoreState
 {
     fn from(value: ParserScoreState) -> Self
     {
         Self {
             max_combo: value.max_combo as _,
+            n_geki:    value.n_geki as _,
+            n_katu:    value.n_katu as _,
             n300:      value.n300 as _,
             n100:      value.n100 as _,
             n50:       value.n50 as _,
@@ -328,3 +500,147 @@ impl From<ParserScoreState> for OsuScoreState
         }
     }
 }
+
+impl From<GameMode> for Mode
+{
+    fn from(value: GameMode) -> Self
+    {
+        match value
+        {
+            GameMode::Osu => Mode::Osu,
+            GameMode::Taiko => Mode::Taiko,
+            GameMode::Catch => Mode::Catch,
+            GameMode::Mania => Mode::Mania,
+        }
+    }
+}
+impl From<Mode> for GameMode
+{
+    fn from(value: Mode) -> Self
+    {
+        match value
+        {
+            Mode::Osu => GameMode::Osu,
+            Mode::Taiko => GameMode::Taiko,
+            Mode::Catch => GameMode::Catch,
+            Mode::Mania => GameMode::Mania,
+        }
+    }
+}
+
+impl From<TaikoDifficultyAttributes> for ParserTaikoDifficulty
+{
+    fn from(value: TaikoDifficultyAttributes) -> Self
+    {
+        Self {
+            stamina:    value.stamina,
+            rhythm:     value.rhythm,
+            colour:     value.colour,
+            peak:       value.peak,
+            hit_window: value.hit_window,
+            stars:      value.stars,
+            max_combo:  value.max_combo as _,
+            is_convert: value.is_convert,
+        }
+    }
+}
+impl From<TaikoPerformanceAttributes> for ParserTaikoPerformance
+{
+    fn from(value: TaikoPerformanceAttributes) -> Self
+    {
+        Self {
+            difficulty:           value.difficulty.into(),
+            pp:                   value.pp,
+            pp_acc:               value.pp_acc,
+            pp_difficulty:        value.pp_difficulty,
+            effective_miss_count: value.effective_miss_count,
+        }
+    }
+}
+impl From<CatchDifficultyAttributes> for ParserCatchDifficulty
+{
+    fn from(value: CatchDifficultyAttributes) -> Self
+    {
+        Self {
+            stars:         value.stars,
+            ar:            value.ar,
+            fruits:        value.n_fruits as _,
+            droplets:      value.n_droplets as _,
+            tiny_droplets: value.n_tiny_droplets as _,
+            max_combo:     value.max_combo() as _,
+            is_convert:    value.is_convert,
+        }
+    }
+}
+impl From<CatchPerformanceAttributes> for ParserCatchPerformance
+{
+    fn from(value: CatchPerformanceAttributes) -> Self
+    {
+        Self {
+            difficulty: value.difficulty.into(),
+            pp:         value.pp,
+        }
+    }
+}
+impl From<ManiaDifficultyAttributes> for ParserManiaDifficulty
+{
+    fn from(value: ManiaDifficultyAttributes) -> Self
+    {
+        Self {
+            stars:      value.stars,
+            hit_window: value.hit_window,
+            max_combo:  value.max_combo as _,
+            is_convert: value.is_convert,
+        }
+    }
+}
+impl From<ManiaPerformanceAttributes> for ParserManiaPerformance
+{
+    fn from(value: ManiaPerformanceAttributes) -> Self
+    {
+        Self {
+            difficulty:    value.difficulty.into(),
+            pp:            value.pp,
+            pp_difficulty: value.pp_difficulty,
+        }
+    }
+}
+impl From<DifficultyAttributes> for ParserModeDifficulty
+{
+    fn from(value: DifficultyAttributes) -> Self
+    {
+        match value
+        {
+            DifficultyAttributes::Osu(x) => ParserModeDifficulty::Osu(x.into()),
+            DifficultyAttributes::Taiko(x) => ParserModeDifficulty::Taiko(x.into()),
+            DifficultyAttributes::Catch(x) => ParserModeDifficulty::Catch(x.into()),
+            DifficultyAttributes::Mania(x) => ParserModeDifficulty::Mania(x.into()),
+        }
+    }
+}
+impl From<PerformanceAttributes> for ParserModeDifficulty
+{
+    fn from(value: PerformanceAttributes) -> Self
+    {
+        match value
+        {
+            PerformanceAttributes::Osu(x) => ParserModeDifficulty::Osu(x.difficulty.into()),
+            PerformanceAttributes::Taiko(x) => ParserModeDifficulty::Taiko(x.difficulty.into()),
+            PerformanceAttributes::Catch(x) => ParserModeDifficulty::Catch(x.difficulty.into()),
+            PerformanceAttributes::Mania(x) => ParserModeDifficulty::Mania(x.difficulty.into()),
+        }
+    }
+}
+impl From<PerformanceAttributes> for ParserModePerformance
+{
+    fn from(value: PerformanceAttributes) -> Self
+    {
+        match value
+        {
+            PerformanceAttributes::Osu(x) => ParserModePerformance::Osu(x.into()),
+            PerformanceAttributes::Taiko(x) => ParserModePerformance::Taiko(x.into()),
+            PerformanceAttributes::Catch(x) => ParserModePerformance::Catch(x.into()),
+            PerformanceAttributes::Mania(x) => ParserModePerformance::Mania(x.into()),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+/// Raw lines of every section of a .osu file.
+///
+/// Used for the values which are not exposed by rosu-pp or libosu.
+#[derive(Default, Debug)]
+pub struct OsuSections
+{
+    pub format_version: u32,
+    sections:           HashMap<String, Vec<String>>,
+}
+
+impl OsuSections
+{
+    pub fn parse(text: &str) -> Self
+    {
+        let mut sections = Self::default();
+        let mut current: Option<String> = None;
+
+        for line in text.trim_start_matches('\u{feff}').lines()
+        {
+            let trimmed = line.trim();
+            if let Some(version) = trimmed.strip_prefix("osu file format v")
+            {
+                sections.format_version = version.parse().unwrap_or_default();
+            }
+            else if trimmed.starts_with('[') && trimmed.ends_with(']')
+            {
+                let name = trimmed[1..trimmed.len() - 1].to_string();
+                sections.sections.entry(name.clone()).or_default();
+                current = Some(name);
+            }
+            else if let Some(section) = &current
+            {
+                // Storyboard commands are indented, so only the line ending is trimmed
+                let line = line.trim_end();
+                if !line.is_empty() && !line.starts_with("//")
+                {
+                    sections
+                        .sections
+                        .get_mut(section)
+                        .unwrap()
+                        .push(line.to_string());
+                }
+            }
+        }
+
+        sections
+    }
+
+    pub fn lines(&self, section: &str) -> &[String]
+    {
+        self.sections
+            .get(section)
+            .map_or(&[], |lines| lines.as_slice())
+    }
+
+    /// Value of a `Key: Value` pair in a section.
+    pub fn value(&self, section: &str, key: &str) -> Option<&str>
+    {
+        self.lines(section).iter().find_map(|line| {
+            let (line_key, value) = line.split_once(':')?;
+            (line_key.trim() == key).then(|| value.trim())
+        })
+    }
+
+    pub fn parse_value<T: std::str::FromStr>(&self, section: &str, key: &str) -> Option<T>
+    {
+        self.value(section, key)?.parse().ok()
+    }
+}
@@ -0,0 +1,658 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    objects::{HitObject, HitObjectKind, PathControlPoint, PathType, Pos2},
+    ParserBeatmap,
+};
+
+const BEZIER_TOLERANCE: f32 = 0.25;
+const CATMULL_DETAIL: usize = 50;
+const CIRCULAR_ARC_TOLERANCE: f32 = 0.1;
+const BASE_SCORING_DISTANCE: f64 = 100.0;
+const LEGACY_LAST_TICK_OFFSET: f64 = 36.0;
+
+/// Piecewise linear approximation of a slider curve, relative to the slider head.
+#[derive(Default, Debug, Clone)]
+pub struct SliderPath
+{
+    pub points:        Vec<Pos2>,
+    cumulative_length: Vec<f64>,
+}
+
+impl SliderPath
+{
+    pub fn new(control_points: &[PathControlPoint], expected_distance: Option<f64>) -> Self
+    {
+        let mut path = Self {
+            points:            calculate_path(control_points),
+            cumulative_length: Vec::new(),
+        };
+        path.calculate_length(control_points, expected_distance);
+        path
+    }
+
+    pub fn distance(&self) -> f64 { self.cumulative_length.last().copied().unwrap_or(0.0) }
+
+    /// Position along the path at `progress` (0 is the head, 1 is the end of the path).
+    pub fn position_at(&self, progress: f64) -> Pos2
+    {
+        if self.points.is_empty()
+        {
+            return Pos2::default();
+        }
+        let distance = progress.clamp(0.0, 1.0) * self.distance();
+        let index = self
+            .cumulative_length
+            .partition_point(|length| *length < distance);
+
+        if index == 0
+        {
+            return self.points[0];
+        }
+        if index >= self.points.len()
+        {
+            return *self.points.last().unwrap();
+        }
+
+        let start = self.points[index - 1];
+        let end = self.points[index];
+        let start_length = self.cumulative_length[index - 1];
+        let end_length = self.cumulative_length[index];
+        if end_length - start_length <= f64::EPSILON
+        {
+            return start;
+        }
+        let weight = (distance - start_length) / (end_length - start_length);
+        start + (end - start) * weight as f32
+    }
+
+    /// Points spaced `spacing` osu!pixels apart along the path, including both ends.
+    pub fn sample(&self, spacing: f64) -> Vec<Pos2>
+    {
+        let distance = self.distance();
+        if spacing <= 0.0 || distance <= 0.0
+        {
+            return self.points.clone();
+        }
+        let count = (distance / spacing).ceil() as usize;
+        (0..=count)
+            .map(|i| self.position_at((i as f64 * spacing / distance).min(1.0)))
+            .collect()
+    }
+
+    fn calculate_length(
+        &mut self, control_points: &[PathControlPoint], expected_distance: Option<f64>,
+    )
+    {
+        let mut calculated_length = 0.0;
+        self.cumulative_length = vec![0.0];
+        for pair in self.points.windows(2)
+        {
+            calculated_length += (pair[1] - pair[0]).length() as f64;
+            self.cumulative_length.push(calculated_length);
+        }
+
+        let expected_distance = match expected_distance
+        {
+            Some(distance) if distance != calculated_length => distance,
+            _ => return,
+        };
+
+        // osu!stable does not extend sliders whose last two control points are equal
+        let len = control_points.len();
+        if len >= 2
+            && control_points[len - 1].pos == control_points[len - 2].pos
+            && expected_distance > calculated_length
+        {
+            return;
+        }
+
+        self.cumulative_length.pop();
+        let mut end_index = self.points.len() as isize - 1;
+
+        if calculated_length > expected_distance
+        {
+            while self
+                .cumulative_length
+                .last()
+                .is_some_and(|length| *length >= expected_distance)
+            {
+                self.cumulative_length.pop();
+                self.points.remove(end_index as usize);
+                end_index -= 1;
+            }
+        }
+
+        if end_index <= 0
+        {
+            self.cumulative_length.push(0.0);
+            return;
+        }
+
+        let end_index = end_index as usize;
+        let direction = (self.points[end_index] - self.points[end_index - 1]).normalize();
+        let remaining = expected_distance - self.cumulative_length.last().unwrap();
+        self.points[end_index] = self.points[end_index - 1] + direction * remaining as f32;
+        self.cumulative_length.push(expected_distance);
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum SliderEventKind
+{
+    #[default]
+    Tick,
+    Repeat,
+    LegacyLastTick,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SliderEvent
+{
+    pub kind: SliderEventKind,
+    pub time: f64,
+    pub pos:  Pos2,
+}
+
+/// Timing and nested objects of a slider, with positions in playfield coordinates.
+#[derive(Default, Debug, Clone)]
+pub struct SliderData
+{
+    pub path:          SliderPath,
+    pub start_time:    f64,
+    pub end_time:      f64,
+    pub span_count:    usize,
+    pub span_duration: f64,
+    pub velocity:      f64,
+    pub events:        Vec<SliderEvent>,
+}
+
+/// Evaluated slider, with positions in playfield coordinates.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserSlider
+{
+    /// Index of the slider in [`ParserBeatmap::hit_objects`].
+    pub object_index:  usize,
+    pub start_time:    f64,
+    pub end_time:      f64,
+    pub span_count:    usize,
+    pub span_duration: f64,
+    /// Velocity in osu!pixels per ms.
+    pub velocity:      f64,
+    /// Length of the path after truncating or extending it to `pixel_len`.
+    pub length:        f64,
+    pub path:          Vec<Pos2>,
+    /// Position of the slider ball at the end time.
+    pub end_position:  Pos2,
+    pub ticks:         Vec<SliderEvent>,
+    pub repeats:       Vec<SliderEvent>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserSliders
+{
+    pub sliders: Vec<ParserSlider>,
+}
+
+impl SliderData
+{
+    pub fn events_of_kind(&self, kind: SliderEventKind) -> impl Iterator<Item = &SliderEvent>
+    {
+        self.events.iter().filter(move |event| event.kind == kind)
+    }
+
+    /// Position of the slider ball at the end time, relative to the slider head.
+    pub fn end_position(&self) -> Pos2 { self.position_at_time(self.end_time) }
+
+    /// Position of the slider ball at `time`, relative to the slider head.
+    pub fn position_at_time(&self, time: f64) -> Pos2
+    {
+        if self.span_duration <= 0.0
+        {
+            return self.path.position_at(0.0);
+        }
+        let spans =
+            ((time - self.start_time) / self.span_duration).clamp(0.0, self.span_count as f64);
+        let span = (spans.floor() as usize).min(self.span_count - 1);
+        let progress = spans - span as f64;
+        self.path.position_at(
+            if span % 2 == 1
+            {
+                1.0 - progress
+            }
+            else
+            {
+                progress
+            },
+        )
+    }
+}
+
+impl ParserBeatmap
+{
+    /// Builds the path, duration and ticks of a slider hit object.
+    ///
+    /// Returns [`None`] if the object is not a slider.
+    pub fn slider_data(&self, object: &HitObject) -> Option<SliderData>
+    {
+        let (pixel_len, repeats, control_points) = match &object.kind
+        {
+            HitObjectKind::Slider {
+                pixel_len,
+                repeats,
+                control_points,
+                ..
+            } => (*pixel_len, *repeats, control_points),
+            _ => return None,
+        };
+
+        let path = SliderPath::new(control_points, pixel_len);
+        let span_count = repeats + 1;
+        let scoring_distance = BASE_SCORING_DISTANCE
+            * self.slider_multiplier
+            * self.slider_velocity_at(object.start_time);
+        let velocity = scoring_distance / self.beat_length_at(object.start_time);
+        let tick_distance = scoring_distance / self.tick_rate;
+
+        let length = path.distance();
+        let span_duration = if velocity > 0.0
+        {
+            length / velocity
+        }
+        else
+        {
+            0.0
+        };
+        let end_time = object.start_time + span_duration * span_count as f64;
+        let min_distance_from_end = velocity * 10.0;
+
+        let mut events = Vec::new();
+        for span in 0..span_count
+        {
+            let span_start_time = object.start_time + span as f64 * span_duration;
+            let reversed = span % 2 == 1;
+
+            let mut ticks = Vec::new();
+            if tick_distance > 0.0 && length > 0.0
+            {
+                let mut distance = tick_distance;
+                while distance < length - min_distance_from_end
+                {
+                    let path_progress = distance / length;
+                    let time_progress = if reversed
+                    {
+                        1.0 - path_progress
+                    }
+                    else
+                    {
+                        path_progress
+                    };
+                    ticks.push(SliderEvent {
+                        kind: SliderEventKind::Tick,
+                        time: span_start_time + time_progress * span_duration,
+                        pos:  object.pos + path.position_at(path_progress),
+                    });
+                    distance += tick_distance;
+                }
+            }
+            if reversed
+            {
+                ticks.reverse();
+            }
+            events.extend(ticks);
+
+            if span < span_count - 1
+            {
+                events.push(SliderEvent {
+                    kind: SliderEventKind::Repeat,
+                    time: span_start_time + span_duration,
+                    pos:  object.pos + path.position_at(if reversed { 0.0 } else { 1.0 }),
+                });
+            }
+        }
+
+        let legacy_last_tick_time = (object.start_time + (end_time - object.start_time) / 2.0)
+            .max(end_time - LEGACY_LAST_TICK_OFFSET);
+        let mut data = SliderData {
+            path,
+            start_time: object.start_time,
+            end_time,
+            span_count,
+            span_duration,
+            velocity,
+            events,
+        };
+        data.events.push(SliderEvent {
+            kind: SliderEventKind::LegacyLastTick,
+            time: legacy_last_tick_time,
+            pos:  object.pos + data.position_at_time(legacy_last_tick_time),
+        });
+
+        Some(data)
+    }
+
+    /// Evaluates every slider of the beatmap.
+    ///
+    /// If `sample_distance` is provided, the path is resampled to points that many osu!pixels
+    /// apart, otherwise the points of the curve approximation are returned.
+    pub fn sliders(&self, sample_distance: Option<f64>) -> ParserSliders
+    {
+        let sliders = self
+            .hit_objects
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(object_index, object)| {
+                let data = self.slider_data(object)?;
+                let path = match sample_distance
+                {
+                    Some(spacing) => data.path.sample(spacing),
+                    None => data.path.points.clone(),
+                };
+                Some(ParserSlider {
+                    object_index,
+                    start_time: data.start_time,
+                    end_time: data.end_time,
+                    span_count: data.span_count,
+                    span_duration: data.span_duration,
+                    velocity: data.velocity,
+                    length: data.path.distance(),
+                    path: path.into_iter().map(|point| object.pos + point).collect(),
+                    end_position: object.pos + data.end_position(),
+                    ticks: data
+                        .events_of_kind(SliderEventKind::Tick)
+                        .copied()
+                        .collect(),
+                    repeats: data
+                        .events_of_kind(SliderEventKind::Repeat)
+                        .copied()
+                        .collect(),
+                })
+            })
+            .collect();
+
+        ParserSliders { sliders }
+    }
+
+    /// Beat length of the uninherited timing point active at `time`.
+    pub fn beat_length_at(&self, time: f64) -> f64
+    {
+        let uninherited = self
+            .timing_points
+            .iter()
+            .flatten()
+            .filter(|point| point.uninherited);
+        let first = uninherited.clone().next();
+        uninherited
+            .take_while(|point| point.time <= time)
+            .last()
+            .or(first)
+            .map_or(1000.0, |point| point.beat_length)
+    }
+
+    /// Slider velocity multiplier of the timing point active at `time`, uninherited points reset
+    /// it to 1.
+    pub fn slider_velocity_at(&self, time: f64) -> f64
+    {
+        self.timing_points
+            .iter()
+            .flatten()
+            .take_while(|point| point.time <= time)
+            .last()
+            .map_or(1.0, |point| point.slider_velocity)
+    }
+}
+
+fn calculate_path(control_points: &[PathControlPoint]) -> Vec<Pos2>
+{
+    let mut path: Vec<Pos2> = Vec::new();
+    let vertices: Vec<Pos2> = control_points.iter().map(|point| point.pos).collect();
+    let mut start = 0;
+
+    for i in 0..control_points.len()
+    {
+        if control_points[i].kind.is_none() && i < control_points.len() - 1
+        {
+            continue;
+        }
+
+        let segment = &vertices[start..=i];
+        let kind = control_points[start].kind.unwrap_or(PathType::Linear);
+        if segment.len() == 1
+        {
+            path.push(segment[0]);
+        }
+        else if segment.len() > 1
+        {
+            let sub_path = calculate_sub_path(segment, kind);
+            let skip_first = matches!((path.last(), sub_path.first()), (Some(last), Some(first)) if last == first);
+            path.extend(sub_path.into_iter().skip(skip_first as usize));
+        }
+        start = i;
+    }
+
+    path
+}
+
+fn calculate_sub_path(segment: &[Pos2], kind: PathType) -> Vec<Pos2>
+{
+    match kind
+    {
+        PathType::Linear => segment.to_vec(),
+        PathType::PerfectCurve if segment.len() == 3 =>
+        {
+            circular_arc(segment).unwrap_or_else(|| bezier(segment))
+        }
+        PathType::PerfectCurve | PathType::Bezier => bezier(segment),
+        PathType::Catmull => catmull(segment),
+    }
+}
+
+fn bezier_is_flat_enough(points: &[Pos2]) -> bool
+{
+    points.windows(3).all(|window| {
+        (window[0] - window[1] * 2.0 + window[2]).length_squared()
+            <= BEZIER_TOLERANCE * BEZIER_TOLERANCE * 4.0
+    })
+}
+
+fn bezier_subdivide(points: &[Pos2], left: &mut [Pos2], right: &mut [Pos2])
+{
+    let count = points.len();
+    let mut midpoints = points.to_vec();
+    for i in 0..count
+    {
+        left[i] = midpoints[0];
+        right[count - i - 1] = midpoints[count - i - 1];
+        for j in 0..count - i - 1
+        {
+            midpoints[j] = (midpoints[j] + midpoints[j + 1]) * 0.5;
+        }
+    }
+}
+
+fn bezier_approximate(points: &[Pos2], output: &mut Vec<Pos2>)
+{
+    let count = points.len();
+    let mut left = vec![Pos2::default(); count * 2 - 1];
+    let mut right = vec![Pos2::default(); count];
+    bezier_subdivide(points, &mut left[..count], &mut right);
+    left[count..].copy_from_slice(&right[1..]);
+
+    output.push(points[0]);
+    for i in 1..count - 1
+    {
+        let index = 2 * i;
+        output.push((left[index - 1] + left[index] * 2.0 + left[index + 1]) * 0.25);
+    }
+}
+
+fn bezier(points: &[Pos2]) -> Vec<Pos2>
+{
+    let mut output = Vec::new();
+    if points.is_empty()
+    {
+        return output;
+    }
+
+    let count = points.len();
+    let mut to_flatten = vec![points.to_vec()];
+    while let Some(parent) = to_flatten.pop()
+    {
+        if bezier_is_flat_enough(&parent)
+        {
+            bezier_approximate(&parent, &mut output);
+            continue;
+        }
+        let mut left = vec![Pos2::default(); count];
+        let mut right = vec![Pos2::default(); count];
+        bezier_subdivide(&parent, &mut left, &mut right);
+        to_flatten.push(right);
+        to_flatten.push(left);
+    }
+
+    output.push(points[count - 1]);
+    output
+}
+
+fn catmull_find_point(v1: Pos2, v2: Pos2, v3: Pos2, v4: Pos2, t: f32) -> Pos2
+{
+    let t2 = t * t;
+    let t3 = t * t2;
+    let axis = |a1: f32, a2: f32, a3: f32, a4: f32| {
+        0.5 * (2.0 * a2
+            + (-a1 + a3) * t
+            + (2.0 * a1 - 5.0 * a2 + 4.0 * a3 - a4) * t2
+            + (-a1 + 3.0 * a2 - 3.0 * a3 + a4) * t3)
+    };
+    Pos2 {
+        x: axis(v1.x, v2.x, v3.x, v4.x),
+        y: axis(v1.y, v2.y, v3.y, v4.y),
+    }
+}
+
+fn catmull(points: &[Pos2]) -> Vec<Pos2>
+{
+    let count = points.len();
+    let mut output = Vec::with_capacity((count - 1) * CATMULL_DETAIL * 2);
+    for i in 0..count - 1
+    {
+        let v1 = if i > 0 { points[i - 1] } else { points[i] };
+        let v2 = points[i];
+        let v3 = if i < count - 1
+        {
+            points[i + 1]
+        }
+        else
+        {
+            v2 + v2 - v1
+        };
+        let v4 = if i < count - 2
+        {
+            points[i + 2]
+        }
+        else
+        {
+            v3 + v3 - v2
+        };
+
+        for c in 0..CATMULL_DETAIL
+        {
+            output.push(catmull_find_point(
+                v1,
+                v2,
+                v3,
+                v4,
+                c as f32 / CATMULL_DETAIL as f32,
+            ));
+            output.push(catmull_find_point(
+                v1,
+                v2,
+                v3,
+                v4,
+                (c + 1) as f32 / CATMULL_DETAIL as f32,
+            ));
+        }
+    }
+    output
+}
+
+fn circular_arc(points: &[Pos2]) -> Option<Vec<Pos2>>
+{
+    let (a, b, c) = (points[0], points[1], points[2]);
+
+    let a_sq = (b - c).length_squared() as f64;
+    let b_sq = (a - c).length_squared() as f64;
+    let c_sq = (a - b).length_squared() as f64;
+    if a_sq.abs() < 1e-3 || b_sq.abs() < 1e-3 || c_sq.abs() < 1e-3
+    {
+        return None;
+    }
+
+    let s = a_sq * (b_sq + c_sq - a_sq);
+    let t = b_sq * (a_sq + c_sq - b_sq);
+    let u = c_sq * (a_sq + b_sq - c_sq);
+    let sum = s + t + u;
+    if sum.abs() < 1e-3
+    {
+        return None;
+    }
+
+    let centre = (a * s as f32 + b * t as f32 + c * u as f32) * (1.0 / sum as f32);
+    let d_a = a - centre;
+    let d_c = c - centre;
+    let radius = d_a.length() as f64;
+
+    let theta_start = (d_a.y as f64).atan2(d_a.x as f64);
+    let mut theta_end = (d_c.y as f64).atan2(d_c.x as f64);
+    while theta_end < theta_start
+    {
+        theta_end += 2.0 * PI;
+    }
+
+    let mut direction = 1.0;
+    let mut theta_range = theta_end - theta_start;
+
+    // Decide in which direction to draw the circle, depending on which side of AC B lies
+    let ortho_a_to_c = Pos2 {
+        x: c.y - a.y,
+        y: -(c.x - a.x),
+    };
+    let a_to_b = b - a;
+    if ortho_a_to_c.x * a_to_b.x + ortho_a_to_c.y * a_to_b.y < 0.0
+    {
+        direction = -direction;
+        theta_range = 2.0 * PI - theta_range;
+    }
+
+    let amount_points = if 2.0 * radius <= CIRCULAR_ARC_TOLERANCE as f64
+    {
+        2
+    }
+    else
+    {
+        let step = 2.0 * (1.0 - CIRCULAR_ARC_TOLERANCE as f64 / radius).acos();
+        ((theta_range / step).ceil() as usize).max(2)
+    };
+
+    Some(
+        (0..amount_points)
+            .map(|i| {
+                let fract = i as f64 / (amount_points - 1) as f64;
+                let theta = theta_start + direction * fract * theta_range;
+                centre
+                    + Pos2 {
+                        x: (theta.cos() * radius) as f32,
+                        y: (theta.sin() * radius) as f32,
+                    }
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    mods::{ar_to_ms, scale_difficulty},
+    objects::{HitObjectKind, Pos2},
+    ParserBeatmap,
+};
+use crate::replay::{Mode, Mods};
+
+/// Objects closer than this many osu!pixels are stacked.
+const STACK_DISTANCE: f32 = 3.0;
+/// Beatmaps older than this format version use the old stacking algorithm.
+const STACKING_FORMAT_VERSION: u32 = 6;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StackedHitObject
+{
+    /// Position after the HR and Mirror flips, without the stack offset.
+    pub position:             Pos2,
+    /// Positive heights move the object up and to the left, negative ones down and to the right.
+    pub stack_height:         i32,
+    pub stacked_position:     Pos2,
+    /// Position of the slider ball at the end time, the stacked position for other objects.
+    pub stacked_end_position: Pos2,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserStackedObjects
+{
+    /// Offset applied per stack height on both axes.
+    pub stack_offset: f32,
+    /// Same order as [`ParserBeatmap::hit_objects`].
+    pub objects:      Vec<StackedHitObject>,
+}
+
+#[derive(PartialEq)]
+enum StackKind
+{
+    Circle,
+    Slider,
+    Spinner,
+}
+
+struct StackObject
+{
+    kind:         StackKind,
+    start_time:   f64,
+    end_time:     f64,
+    pos:          Pos2,
+    /// Position at the end time, which is the head or tail depending on the repeats.
+    end_pos:      Pos2,
+    /// Position at the end of the path, used by the old algorithm.
+    path_end:     Pos2,
+    stack_height: i32,
+}
+
+impl ParserBeatmap
+{
+    /// Calculates the stack height and stacked position of every hit object.
+    ///
+    /// The objects are flipped like [`ParserBeatmap::flipped`] before they are stacked, and EZ/HR
+    /// change the stack offset through the circle size. Objects of other modes are never stacked.
+    pub fn stacking(&self, mods: Mods) -> ParserStackedObjects
+    {
+        let flip = |pos: Pos2| self.flip_position(pos, mods);
+
+        let mut objects = self
+            .hit_objects
+            .iter()
+            .flatten()
+            .map(|object| {
+                let (kind, end_time, end_pos, path_end) = match &object.kind
+                {
+                    HitObjectKind::Slider { .. } =>
+                    {
+                        let data = self.slider_data(object).unwrap();
+                        (
+                            StackKind::Slider,
+                            data.end_time,
+                            object.pos + data.end_position(),
+                            object.pos + data.path.position_at(1.0),
+                        )
+                    }
+                    HitObjectKind::Spinner { end_time } | HitObjectKind::Hold { end_time } =>
+                    {
+                        (StackKind::Spinner, *end_time, object.pos, object.pos)
+                    }
+                    HitObjectKind::Circle =>
+                    {
+                        (StackKind::Circle, object.start_time, object.pos, object.pos)
+                    }
+                };
+                StackObject {
+                    kind,
+                    start_time: object.start_time,
+                    end_time,
+                    pos: flip(object.pos),
+                    end_pos: flip(end_pos),
+                    path_end: flip(path_end),
+                    stack_height: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if self.mode == Mode::Osu && !objects.is_empty()
+        {
+            let preempt = ar_to_ms(scale_difficulty(self.ar, mods, 1.4)) as f64;
+            let stack_threshold = preempt * self.stack_leniency as f64;
+            if self.format_version < STACKING_FORMAT_VERSION
+            {
+                apply_stacking_old(&mut objects, stack_threshold);
+            }
+            else
+            {
+                apply_stacking(&mut objects, stack_threshold);
+            }
+        }
+
+        let cs = scale_difficulty(self.cs, mods, 1.3);
+        let scale = (1.0 - 0.7 * (cs - 5.0) / 5.0) / 2.0;
+        let stack_offset = scale * -6.4;
+
+        ParserStackedObjects {
+            stack_offset,
+            objects: objects
+                .iter()
+                .map(|object| {
+                    let offset = stack_offset * object.stack_height as f32;
+                    let offset = Pos2 {
+                        x: offset,
+                        y: offset,
+                    };
+                    StackedHitObject {
+                        position:             object.pos,
+                        stack_height:         object.stack_height,
+                        stacked_position:     object.pos + offset,
+                        stacked_end_position: object.end_pos + offset,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Stacking of format version 6 and newer, objects are stacked backwards from the last one.
+fn apply_stacking(objects: &mut [StackObject], stack_threshold: f64)
+{
+    let close = |a: Pos2, b: Pos2| a.distance(b) < STACK_DISTANCE;
+    for i in (1..objects.len()).rev()
+    {
+        if objects[i].stack_height != 0 || objects[i].kind == StackKind::Spinner
+        {
+            continue;
+        }
+
+        let mut current = i;
+        let mut n = i;
+        if objects[i].kind == StackKind::Circle
+        {
+            while n > 0
+            {
+                n -= 1;
+                if objects[n].kind == StackKind::Spinner
+                {
+                    continue;
+                }
+                if objects[current].start_time - objects[n].end_time > stack_threshold
+                {
+                    break;
+                }
+                // Objects stacked onto a slider end move down and to the right instead
+                if objects[n].kind == StackKind::Slider
+                    && close(objects[n].end_pos, objects[current].pos)
+                {
+                    let offset = objects[current].stack_height - objects[n].stack_height + 1;
+                    let end_pos = objects[n].end_pos;
+                    for object in &mut objects[n + 1..=i]
+                    {
+                        if close(end_pos, object.pos)
+                        {
+                            object.stack_height -= offset;
+                        }
+                    }
+                    break;
+                }
+                if close(objects[n].pos, objects[current].pos)
+                {
+                    objects[n].stack_height = objects[current].stack_height + 1;
+                    current = n;
+                }
+            }
+        }
+        else if objects[i].kind == StackKind::Slider
+        {
+            while n > 0
+            {
+                n -= 1;
+                if objects[n].kind == StackKind::Spinner
+                {
+                    continue;
+                }
+                if objects[current].start_time - objects[n].start_time > stack_threshold
+                {
+                    break;
+                }
+                if close(objects[n].end_pos, objects[current].pos)
+                {
+                    objects[n].stack_height = objects[current].stack_height + 1;
+                    current = n;
+                }
+            }
+        }
+    }
+}
+
+/// Stacking of format versions before 6.
+fn apply_stacking_old(objects: &mut [StackObject], stack_threshold: f64)
+{
+    for i in 0..objects.len()
+    {
+        if objects[i].stack_height != 0 && objects[i].kind != StackKind::Slider
+        {
+            continue;
+        }
+
+        let mut start_time = objects[i].end_time;
+        let mut slider_stack = 0;
+        for j in i + 1..objects.len()
+        {
+            if objects[j].start_time - stack_threshold > start_time
+            {
+                break;
+            }
+
+            if objects[j].pos.distance(objects[i].pos) < STACK_DISTANCE
+            {
+                objects[i].stack_height += 1;
+                start_time = objects[j].start_time;
+            }
+            else if objects[j].pos.distance(objects[i].path_end) < STACK_DISTANCE
+            {
+                // Objects stacked onto a slider end move down and to the right instead
+                slider_stack += 1;
+                objects[j].stack_height -= slider_stack;
+                start_time = objects[j].start_time;
+            }
+        }
+    }
+}
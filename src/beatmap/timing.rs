@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{sections::OsuSections, ParserBeatmap, ParserTimingPoint};
+
+const EFFECT_KIAI: u32 = 1;
+const EFFECT_OMIT_FIRST_BARLINE: u32 = 8;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum SampleSet
+{
+    /// Uses the sample set of the beatmap.
+    #[default]
+    Default = 0,
+    Normal  = 1,
+    Soft    = 2,
+    Drum    = 3,
+}
+impl From<u32> for SampleSet
+{
+    fn from(value: u32) -> Self
+    {
+        match value
+        {
+            1 => SampleSet::Normal,
+            2 => SampleSet::Soft,
+            3 => SampleSet::Drum,
+            _ => SampleSet::Default,
+        }
+    }
+}
+
+impl SampleSet
+{
+    /// Parses the `SampleSet` name of the `[General]` section.
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        match name
+        {
+            "Normal" => Some(SampleSet::Normal),
+            "Soft" => Some(SampleSet::Soft),
+            "Drum" => Some(SampleSet::Drum),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            SampleSet::Default | SampleSet::Normal => "Normal",
+            SampleSet::Soft => "Soft",
+            SampleSet::Drum => "Drum",
+        }
+    }
+}
+
+impl ParserTimingPoint
+{
+    /// Parses a `time,beatLength,meter,sampleSet,sampleIndex,volume,uninherited,effects` line.
+    ///
+    /// Older format versions omit the trailing fields, they use the defaults of osu!stable.
+    pub fn parse(line: &str) -> Option<Self>
+    {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        let field = |index: usize| fields.get(index).and_then(|x| x.parse::<f64>().ok());
+
+        let time = field(0)?;
+        let beat_length = field(1)?;
+        let uninherited = field(6).map_or(beat_length >= 0.0, |x| x != 0.0);
+        let effects = field(7).unwrap_or(0.0) as u32;
+        let slider_velocity = if uninherited || beat_length >= 0.0
+        {
+            1.0
+        }
+        else
+        {
+            (-100.0 / beat_length).clamp(0.1, 10.0)
+        };
+
+        Some(Self {
+            time,
+            beat_length,
+            meter: field(2).map_or(4, |x| x as u32),
+            sample_set: SampleSet::from(field(3).unwrap_or(0.0) as u32),
+            sample_index: field(4).unwrap_or(0.0) as u32,
+            volume: field(5).map_or(100, |x| x as u32),
+            uninherited,
+            kiai: effects & EFFECT_KIAI != 0,
+            omit_first_barline: effects & EFFECT_OMIT_FIRST_BARLINE != 0,
+            slider_velocity,
+        })
+    }
+
+    /// BPM of uninherited points.
+    pub fn bpm(&self) -> Option<f64>
+    {
+        (self.uninherited && self.beat_length > 0.0).then(|| 60000.0 / self.beat_length)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserKiaiInterval
+{
+    pub start_time: f64,
+    pub end_time:   f64,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserSliderVelocityRange
+{
+    pub start_time:      f64,
+    pub end_time:        f64,
+    pub slider_velocity: f64,
+}
+
+/// Kiai sections and slider velocity ranges, derived from the timing points.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserTimingRanges
+{
+    pub kiai_intervals:         Vec<ParserKiaiInterval>,
+    pub slider_velocity_ranges: Vec<ParserSliderVelocityRange>,
+}
+
+impl ParserBeatmap
+{
+    pub fn parse_timing_points(sections: &OsuSections) -> Vec<ParserTimingPoint>
+    {
+        let mut points = sections
+            .lines("TimingPoints")
+            .iter()
+            .filter_map(|line| ParserTimingPoint::parse(line))
+            .collect::<Vec<_>>();
+        // Stable sort, the order of points at the same time matters
+        points.sort_by(|a, b| a.time.total_cmp(&b.time));
+        points
+    }
+
+    pub fn timing_ranges(&self) -> ParserTimingRanges
+    {
+        ParserTimingRanges {
+            kiai_intervals:         self.get_kiai_intervals(),
+            slider_velocity_ranges: self.get_slider_velocity_ranges(),
+        }
+    }
+
+    /// Kiai sections, the last one ends at the end of the beatmap.
+    pub fn get_kiai_intervals(&self) -> Vec<ParserKiaiInterval>
+    {
+        let mut intervals: Vec<ParserKiaiInterval> = Vec::new();
+        let mut start = None;
+        for point in self.timing_points.iter().flatten()
+        {
+            match (start, point.kiai)
+            {
+                (None, true) => start = Some(point.time),
+                (Some(start_time), false) =>
+                {
+                    intervals.push(ParserKiaiInterval {
+                        start_time,
+                        end_time: point.time,
+                    });
+                    start = None;
+                }
+                _ =>
+                {}
+            }
+        }
+        if let Some(start_time) = start
+        {
+            intervals.push(ParserKiaiInterval {
+                start_time,
+                end_time: (self.map_length as f64).max(start_time),
+            });
+        }
+        intervals.retain(|interval| interval.end_time > interval.start_time);
+        intervals
+    }
+
+    /// Ranges of constant slider velocity, the last one ends at the end of the beatmap.
+    pub fn get_slider_velocity_ranges(&self) -> Vec<ParserSliderVelocityRange>
+    {
+        let mut ranges: Vec<ParserSliderVelocityRange> = Vec::new();
+        for point in self.timing_points.iter().flatten()
+        {
+            if let Some(last) = ranges.last_mut()
+            {
+                if last.slider_velocity == point.slider_velocity
+                {
+                    continue;
+                }
+                last.end_time = point.time;
+                if last.end_time <= last.start_time
+                {
+                    ranges.pop();
+                }
+            }
+            if ranges
+                .last()
+                .is_some_and(|last| last.slider_velocity == point.slider_velocity)
+            {
+                continue;
+            }
+            ranges.push(ParserSliderVelocityRange {
+                start_time:      point.time,
+                end_time:        point.time,
+                slider_velocity: point.slider_velocity,
+            });
+        }
+        if let Some(last) = ranges.last_mut()
+        {
+            last.end_time = (self.map_length as f64).max(last.start_time);
+        }
+        ranges
+    }
+}
@@ -0,0 +1,259 @@
+use std::io::Write;
+
+use itertools::Itertools;
+
+use super::{
+    objects::{HitObject, HitObjectKind, PathType},
+    ParserBeatmap,
+};
+use crate::replay::ParserResult;
+
+const FORMAT_VERSION: u32 = 14;
+const DEFAULT_HIT_SAMPLE: &str = "0:0:0:0:";
+const DEFAULT_EDGE_SET: &str = "0:0";
+
+impl ParserBeatmap
+{
+    /// Writes the beatmap in the osu file format v14.
+    pub fn write<W: Write>(&self, out: &mut W) -> ParserResult<()>
+    {
+        writeln!(out, "osu file format v{}", FORMAT_VERSION)?;
+
+        writeln!(out, "\n[General]")?;
+        writeln!(out, "AudioFilename: {}", self.audio_filename)?;
+        writeln!(out, "AudioLeadIn: {}", self.audio_lead_in)?;
+        writeln!(out, "PreviewTime: {}", self.preview_time)?;
+        writeln!(out, "Countdown: {}", self.countdown)?;
+        writeln!(out, "SampleSet: {}", self.sample_set.name())?;
+        writeln!(out, "StackLeniency: {}", self.stack_leniency)?;
+        writeln!(out, "Mode: {}", self.mode as u8)?;
+        writeln!(out, "LetterboxInBreaks: {}", self.letterbox_in_breaks as u8)?;
+        writeln!(out, "EpilepsyWarning: {}", self.epilepsy_warning as u8)?;
+        writeln!(
+            out,
+            "WidescreenStoryboard: {}",
+            self.widescreen_storyboard as u8
+        )?;
+
+        if !self.editor.is_empty()
+        {
+            writeln!(out, "\n[Editor]")?;
+            for line in &self.editor
+            {
+                writeln!(out, "{}", line)?;
+            }
+        }
+
+        writeln!(out, "\n[Metadata]")?;
+        writeln!(out, "Title:{}", self.title)?;
+        writeln!(out, "TitleUnicode:{}", self.title_unicode)?;
+        writeln!(out, "Artist:{}", self.artist)?;
+        writeln!(out, "ArtistUnicode:{}", self.artist_unicode)?;
+        writeln!(out, "Creator:{}", self.creator)?;
+        writeln!(out, "Version:{}", self.diff_name)?;
+        writeln!(out, "Source:{}", self.source)?;
+        writeln!(out, "Tags:{}", self.tags.join(" "))?;
+        if let Some(beatmap_id) = self.beatmap_id
+        {
+            writeln!(out, "BeatmapID:{}", beatmap_id)?;
+        }
+        if let Some(beatmapset_id) = self.beatmapset_id
+        {
+            writeln!(out, "BeatmapSetID:{}", beatmapset_id)?;
+        }
+
+        writeln!(out, "\n[Difficulty]")?;
+        writeln!(out, "HPDrainRate:{}", self.hp)?;
+        writeln!(out, "CircleSize:{}", self.cs)?;
+        writeln!(out, "OverallDifficulty:{}", self.od)?;
+        writeln!(out, "ApproachRate:{}", self.ar)?;
+        writeln!(out, "SliderMultiplier:{}", self.slider_multiplier)?;
+        writeln!(out, "SliderTickRate:{}", self.tick_rate)?;
+
+        writeln!(out, "\n[Events]")?;
+        if let Some(background) = &self.background
+        {
+            writeln!(
+                out,
+                "0,0,\"{}\",{},{}",
+                background, self.background_offset.x, self.background_offset.y
+            )?;
+        }
+        if let Some(video) = &self.video
+        {
+            writeln!(
+                out,
+                "Video,{},\"{}\",{},{}",
+                self.video_start_time, video, self.video_offset.x, self.video_offset.y
+            )?;
+        }
+        for break_period in self.breaks.iter().flatten()
+        {
+            writeln!(
+                out,
+                "2,{},{}",
+                break_period.start_time, break_period.end_time
+            )?;
+        }
+        for line in &self.storyboard
+        {
+            writeln!(out, "{}", line)?;
+        }
+
+        writeln!(out, "\n[TimingPoints]")?;
+        for point in self.timing_points.iter().flatten()
+        {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                point.time.round(),
+                point.beat_length,
+                point.meter,
+                point.sample_set as u8,
+                point.sample_index,
+                point.volume,
+                point.uninherited as u8,
+                point.kiai as u8 | (point.omit_first_barline as u8) << 3
+            )?;
+        }
+
+        let slider_colors = [
+            ("SliderTrackOverride", &self.slider_track_override),
+            ("SliderBorder", &self.slider_border),
+        ];
+        if !self.combo_colors.is_empty() || slider_colors.iter().any(|(_, color)| color.is_some())
+        {
+            writeln!(out, "\n[Colours]")?;
+            for (i, color) in self.combo_colors.iter().enumerate()
+            {
+                writeln!(
+                    out,
+                    "Combo{} : {},{},{}",
+                    i + 1,
+                    color.red,
+                    color.green,
+                    color.blue
+                )?;
+            }
+            for (key, color) in slider_colors
+            {
+                if let Some(color) = color
+                {
+                    writeln!(
+                        out,
+                        "{} : {},{},{}",
+                        key, color.red, color.green, color.blue
+                    )?;
+                }
+            }
+        }
+
+        writeln!(out, "\n[HitObjects]")?;
+        for object in self.hit_objects.iter().flatten()
+        {
+            writeln!(out, "{}", self.hit_object_line(object))?;
+        }
+
+        Ok(())
+    }
+
+    /// Beatmap in the osu file format v14.
+    pub fn to_osu_string(&self) -> ParserResult<String>
+    {
+        let mut out = Vec::new();
+        self.write(&mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    fn hit_object_line(&self, object: &HitObject) -> String
+    {
+        let kind = match object.kind
+        {
+            HitObjectKind::Circle => 1,
+            HitObjectKind::Slider { .. } => 2,
+            HitObjectKind::Spinner { .. } => 8,
+            HitObjectKind::Hold { .. } => 128,
+        } | (object.new_combo as u8) << 2
+            | (object.combo_offset & 0b111) << 4;
+        let hit_sample = if object.hit_sample.is_empty()
+        {
+            DEFAULT_HIT_SAMPLE
+        }
+        else
+        {
+            &object.hit_sample
+        };
+
+        let mut line = format!(
+            "{},{},{},{},{}",
+            object.pos.x,
+            object.pos.y,
+            object.start_time.round(),
+            kind,
+            object.hit_sound
+        );
+        match &object.kind
+        {
+            HitObjectKind::Circle => line += &format!(",{}", hit_sample),
+            HitObjectKind::Slider {
+                pixel_len,
+                repeats,
+                control_points,
+                edge_sounds,
+            } =>
+            {
+                let curve_type = match control_points.first().and_then(|point| point.kind)
+                {
+                    Some(PathType::Catmull) => 'C',
+                    Some(PathType::Linear) => 'L',
+                    Some(PathType::PerfectCurve) => 'P',
+                    Some(PathType::Bezier) | None => 'B',
+                };
+                let mut curve = curve_type.to_string();
+                for (i, point) in control_points.iter().enumerate().skip(1)
+                {
+                    let pos = object.pos + point.pos;
+                    curve += &format!("|{}:{}", pos.x, pos.y);
+                    // Bezier segments are separated by repeating their last point
+                    if curve_type == 'B' && point.kind.is_some() && i < control_points.len() - 1
+                    {
+                        curve += &format!("|{}:{}", pos.x, pos.y);
+                    }
+                }
+                let length = pixel_len.unwrap_or_else(|| {
+                    self.slider_data(object)
+                        .map_or(0.0, |data| data.path.distance())
+                });
+                line += &format!(",{},{},{}", curve, repeats + 1, length);
+
+                if !edge_sounds.is_empty()
+                    || !object.edge_sets.is_empty()
+                    || !object.hit_sample.is_empty()
+                {
+                    let edges = repeats + 2;
+                    let sounds = (0..edges)
+                        .map(|i| edge_sounds.get(i).copied().unwrap_or(object.hit_sound))
+                        .join("|");
+                    let sets = (0..edges)
+                        .map(|i| {
+                            object
+                                .edge_sets
+                                .get(i)
+                                .map_or(DEFAULT_EDGE_SET, |set| set.as_str())
+                        })
+                        .join("|");
+                    line += &format!(",{},{},{}", sounds, sets, hit_sample);
+                }
+            }
+            HitObjectKind::Spinner { end_time } =>
+            {
+                line += &format!(",{},{}", end_time.round(), hit_sample)
+            }
+            HitObjectKind::Hold { end_time } =>
+            {
+                line += &format!(",{}:{}", end_time.round(), hit_sample)
+            }
+        }
+        line
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::{Read, Write};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::replay::{parse::ULEB128Decode, write::ULEB128Encode, ParserResult};
+
+/// Version written to newly generated collection databases.
+pub const COLLECTION_DATABASE_VERSION: u32 = 20150203;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Collection
+{
+    pub name:           String,
+    /// MD5 hashes of the beatmaps, see [`crate::beatmap::ParserBeatmap::hash`].
+    pub beatmap_hashes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CollectionDatabase
+{
+    pub version:     u32,
+    pub collections: Vec<Collection>,
+}
+
+impl Default for CollectionDatabase
+{
+    fn default() -> Self
+    {
+        Self {
+            version:     COLLECTION_DATABASE_VERSION,
+            collections: Vec::new(),
+        }
+    }
+}
+
+impl CollectionDatabase
+{
+    pub fn parse<R: Read>(database: &mut R) -> ParserResult<Self>
+    {
+        let version = database.read_u32::<LittleEndian>()?;
+        let collection_count = database.read_u32::<LittleEndian>()?;
+        let collections = (0..collection_count)
+            .map(|_| {
+                let name = database.read_uleb128_string()?;
+                let hash_count = database.read_u32::<LittleEndian>()?;
+                let beatmap_hashes = (0..hash_count)
+                    .map(|_| Ok(database.read_uleb128_string()?))
+                    .collect::<ParserResult<Vec<_>>>()?;
+                Ok(Collection {
+                    name,
+                    beatmap_hashes,
+                })
+            })
+            .collect::<ParserResult<Vec<_>>>()?;
+
+        Ok(CollectionDatabase {
+            version,
+            collections,
+        })
+    }
+
+    /// Writes the database in the `collection.db` format.
+    pub fn write<W: Write>(&self, out: &mut W) -> ParserResult<()>
+    {
+        out.write_u32::<LittleEndian>(self.version)?;
+        out.write_u32::<LittleEndian>(self.collections.len() as u32)?;
+        for collection in &self.collections
+        {
+            out.write_uleb128_string(&collection.name)?;
+            out.write_u32::<LittleEndian>(collection.beatmap_hashes.len() as u32)?;
+            for hash in &collection.beatmap_hashes
+            {
+                out.write_uleb128_string(hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Database containing a single collection of the provided beatmap hashes.
+    pub fn from_hashes(name: &str, beatmap_hashes: Vec<String>) -> Self
+    {
+        Self {
+            collections: vec![Collection {
+                name: name.to_string(),
+                beatmap_hashes,
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Adds the collections of `other` to this database.
+    ///
+    /// Collections with the same name are combined, hashes already in the collection are skipped.
+    pub fn merge(&mut self, other: CollectionDatabase)
+    {
+        self.version = self.version.max(other.version);
+        for collection in other.collections
+        {
+            match self
+                .collections
+                .iter_mut()
+                .find(|existing| existing.name == collection.name)
+            {
+                Some(existing) =>
+                {
+                    for hash in collection.beatmap_hashes
+                    {
+                        if !existing.beatmap_hashes.contains(&hash)
+                        {
+                            existing.beatmap_hashes.push(hash);
+                        }
+                    }
+                }
+                None => self.collections.push(collection),
+            }
+        }
+    }
+}
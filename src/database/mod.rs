@@ -0,0 +1,23 @@
+pub mod collection;
+pub mod osu;
+pub mod scores;
+
+use std::io::Read;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::replay::ParserResult;
+
+/// Primitive types shared by the osu! stable database files.
+pub trait DatabaseDecode: Read
+{
+    fn read_bool(&mut self) -> ParserResult<bool> { Ok(self.read_u8()? != 0) }
+
+    /// Reads a date stored in windows ticks, kept as a string like [`crate::replay::Replay::timestamp`].
+    fn read_ticks(&mut self) -> ParserResult<String>
+    {
+        Ok(self.read_u64::<LittleEndian>()?.to_string())
+    }
+}
+
+impl<R: Read + ?Sized> DatabaseDecode for R {}
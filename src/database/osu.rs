@@ -0,0 +1,449 @@
+use std::{convert::TryFrom, io::Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::DatabaseDecode;
+use crate::{
+    beatmap::ParserBeatmap,
+    replay::{parse::ULEB128Decode, Grade, Mode, ParserResult},
+};
+
+/// Entries stop being prefixed with their size in bytes from this version on.
+const ENTRY_SIZE_REMOVED_VERSION: u32 = 20191106;
+/// Difficulty settings are stored as floats and star ratings are cached from this version on.
+const FLOAT_DIFFICULTY_VERSION: u32 = 20140609;
+/// Cached star ratings are stored as floats instead of doubles from this version on.
+const FLOAT_STAR_RATING_VERSION: u32 = 20250107;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum RankedStatus
+{
+    #[default]
+    Unknown     = 0,
+    Unsubmitted = 1,
+    /// Pending, WIP or graveyarded.
+    Pending     = 2,
+    Ranked      = 4,
+    Approved    = 5,
+    Qualified   = 6,
+    Loved       = 7,
+}
+impl From<u8> for RankedStatus
+{
+    fn from(value: u8) -> Self
+    {
+        match value
+        {
+            1 => RankedStatus::Unsubmitted,
+            2 => RankedStatus::Pending,
+            4 => RankedStatus::Ranked,
+            5 => RankedStatus::Approved,
+            6 => RankedStatus::Qualified,
+            7 => RankedStatus::Loved,
+            _ => RankedStatus::Unknown,
+        }
+    }
+}
+
+/// Cached star rating of a mod combination.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StarRating
+{
+    pub mods:  u32,
+    pub stars: f64,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct StarRatingCache
+{
+    pub osu:   Vec<StarRating>,
+    pub taiko: Vec<StarRating>,
+    pub catch: Vec<StarRating>,
+    pub mania: Vec<StarRating>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DatabaseTimingPoint
+{
+    pub beat_length: f64,
+    pub time:        f64,
+    pub uninherited: bool,
+}
+
+/// Grades achieved on the beatmap, [`None`] if it has not been played in that mode or the grade
+/// is unknown.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DatabaseGrades
+{
+    pub osu:   Option<Grade>,
+    pub taiko: Option<Grade>,
+    pub catch: Option<Grade>,
+    pub mania: Option<Grade>,
+}
+
+/// Beatmap entry of an `osu!.db`.
+///
+/// Dates are in windows ticks, drain time is in seconds and every other duration in ms.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DatabaseBeatmap
+{
+    pub artist:               String,
+    pub artist_unicode:       String,
+    pub title:                String,
+    pub title_unicode:        String,
+    pub creator:              String,
+    pub diff_name:            String,
+    pub audio_filename:       String,
+    pub hash:                 String,
+    pub filename:             String,
+    pub ranked_status:        RankedStatus,
+    pub circles:              u16,
+    pub sliders:              u16,
+    pub spinners:             u16,
+    pub last_modified:        String,
+    pub ar:                   f32,
+    pub cs:                   f32,
+    pub hp:                   f32,
+    pub od:                   f32,
+    pub slider_multiplier:    f64,
+    pub star_ratings:         StarRatingCache,
+    pub drain_time:           u32,
+    pub total_time:           u32,
+    pub preview_time:         i32,
+    pub timing_points:        Vec<DatabaseTimingPoint>,
+    pub beatmap_id:           i32,
+    pub beatmapset_id:        i32,
+    pub thread_id:            i32,
+    pub grades:               DatabaseGrades,
+    pub local_offset:         i16,
+    pub stack_leniency:       f32,
+    pub mode:                 Mode,
+    pub source:               String,
+    pub tags:                 String,
+    pub online_offset:        i16,
+    pub title_font:           String,
+    pub unplayed:             bool,
+    pub last_played:          String,
+    pub is_osz2:              bool,
+    pub folder_name:          String,
+    pub last_checked:         String,
+    pub ignore_beatmap_sound: bool,
+    pub ignore_beatmap_skin:  bool,
+    pub disable_storyboard:   bool,
+    pub disable_video:        bool,
+    pub visual_override:      bool,
+    pub mania_scroll_speed:   u8,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct OsuDatabase
+{
+    pub version:          u32,
+    pub folder_count:     u32,
+    pub account_unlocked: bool,
+    pub unlock_date:      String,
+    pub username:         String,
+    pub beatmaps:         Vec<DatabaseBeatmap>,
+    pub permissions:      u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum BeatmapAvailability
+{
+    /// A beatmap with the same hash is in the database.
+    UpToDate,
+    /// The beatmap is in the database, but its hash differs.
+    Outdated,
+    Missing,
+}
+
+#[derive(Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BeatmapCheck
+{
+    pub hash:         String,
+    pub beatmap_id:   Option<i32>,
+    pub availability: BeatmapAvailability,
+    /// Hash of the local version if the beatmap is outdated.
+    pub local_hash:   Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct DatabaseCheck
+{
+    pub beatmaps: Vec<BeatmapCheck>,
+}
+
+impl OsuDatabase
+{
+    pub fn parse<R: Read>(database: &mut R) -> ParserResult<Self>
+    {
+        let version = database.read_u32::<LittleEndian>()?;
+        let folder_count = database.read_u32::<LittleEndian>()?;
+        let account_unlocked = database.read_bool()?;
+        let unlock_date = database.read_ticks()?;
+        let username = database.read_uleb128_string()?;
+
+        let beatmap_count = database.read_u32::<LittleEndian>()?;
+        let beatmaps = (0..beatmap_count)
+            .map(|_| DatabaseBeatmap::parse(database, version))
+            .collect::<ParserResult<Vec<_>>>()?;
+
+        let permissions = database.read_u32::<LittleEndian>()?;
+
+        Ok(OsuDatabase {
+            version,
+            folder_count,
+            account_unlocked,
+            unlock_date,
+            username,
+            beatmaps,
+            permissions,
+        })
+    }
+
+    pub fn find_beatmap(&self, hash: &str) -> Option<&DatabaseBeatmap>
+    {
+        self.beatmaps.iter().find(|beatmap| beatmap.hash == hash)
+    }
+
+    /// Checks whether the beatmap is in the database and if it is the same version.
+    ///
+    /// Other versions are matched by beatmap id, or by beatmapset id and difficulty name for
+    /// unsubmitted beatmaps.
+    pub fn check_beatmap(&self, beatmap: &ParserBeatmap) -> BeatmapCheck
+    {
+        let (availability, local_hash) = if self.find_beatmap(&beatmap.hash).is_some()
+        {
+            (BeatmapAvailability::UpToDate, None)
+        }
+        else
+        {
+            let local = self.beatmaps.iter().find(|local| match beatmap.beatmap_id
+            {
+                Some(id) if id > 0 => local.beatmap_id == id,
+                _ =>
+                {
+                    beatmap
+                        .beatmapset_id
+                        .is_some_and(|id| id > 0 && local.beatmapset_id == id)
+                        && local.diff_name == beatmap.diff_name
+                }
+            });
+            match local
+            {
+                Some(local) => (BeatmapAvailability::Outdated, Some(local.hash.clone())),
+                None => (BeatmapAvailability::Missing, None),
+            }
+        };
+
+        BeatmapCheck {
+            hash: beatmap.hash.clone(),
+            beatmap_id: beatmap.beatmap_id,
+            availability,
+            local_hash,
+        }
+    }
+
+    pub fn check_beatmaps(&self, beatmaps: &[ParserBeatmap]) -> DatabaseCheck
+    {
+        DatabaseCheck {
+            beatmaps: beatmaps
+                .iter()
+                .map(|beatmap| self.check_beatmap(beatmap))
+                .collect(),
+        }
+    }
+}
+
+impl DatabaseBeatmap
+{
+    pub fn parse<R: Read>(database: &mut R, version: u32) -> ParserResult<Self>
+    {
+        if version < ENTRY_SIZE_REMOVED_VERSION
+        {
+            database.read_u32::<LittleEndian>()?;
+        }
+
+        let artist = database.read_uleb128_string()?;
+        let artist_unicode = database.read_uleb128_string()?;
+        let title = database.read_uleb128_string()?;
+        let title_unicode = database.read_uleb128_string()?;
+        let creator = database.read_uleb128_string()?;
+        let diff_name = database.read_uleb128_string()?;
+        let audio_filename = database.read_uleb128_string()?;
+        let hash = database.read_uleb128_string()?;
+        let filename = database.read_uleb128_string()?;
+        let ranked_status = RankedStatus::from(database.read_u8()?);
+        let circles = database.read_u16::<LittleEndian>()?;
+        let sliders = database.read_u16::<LittleEndian>()?;
+        let spinners = database.read_u16::<LittleEndian>()?;
+        let last_modified = database.read_ticks()?;
+
+        let mut read_difficulty = || -> ParserResult<f32> {
+            if version < FLOAT_DIFFICULTY_VERSION
+            {
+                Ok(database.read_u8()? as f32)
+            }
+            else
+            {
+                Ok(database.read_f32::<LittleEndian>()?)
+            }
+        };
+        let ar = read_difficulty()?;
+        let cs = read_difficulty()?;
+        let hp = read_difficulty()?;
+        let od = read_difficulty()?;
+        let slider_multiplier = database.read_f64::<LittleEndian>()?;
+
+        let star_ratings = if version >= FLOAT_DIFFICULTY_VERSION
+        {
+            StarRatingCache {
+                osu:   read_star_ratings(database, version)?,
+                taiko: read_star_ratings(database, version)?,
+                catch: read_star_ratings(database, version)?,
+                mania: read_star_ratings(database, version)?,
+            }
+        }
+        else
+        {
+            StarRatingCache::default()
+        };
+
+        let drain_time = database.read_u32::<LittleEndian>()?;
+        let total_time = database.read_u32::<LittleEndian>()?;
+        let preview_time = database.read_i32::<LittleEndian>()?;
+
+        let timing_point_count = database.read_u32::<LittleEndian>()?;
+        let timing_points = (0..timing_point_count)
+            .map(|_| {
+                Ok(DatabaseTimingPoint {
+                    beat_length: database.read_f64::<LittleEndian>()?,
+                    time:        database.read_f64::<LittleEndian>()?,
+                    uninherited: database.read_bool()?,
+                })
+            })
+            .collect::<ParserResult<Vec<_>>>()?;
+
+        let beatmap_id = database.read_i32::<LittleEndian>()?;
+        let beatmapset_id = database.read_i32::<LittleEndian>()?;
+        let thread_id = database.read_i32::<LittleEndian>()?;
+
+        // 9 means not played, grades this parser does not know are skipped the same way
+        let mut read_grade =
+            || -> ParserResult<Option<Grade>> { Ok(Grade::try_from(database.read_u8()?).ok()) };
+        let grades = DatabaseGrades {
+            osu:   read_grade()?,
+            taiko: read_grade()?,
+            catch: read_grade()?,
+            mania: read_grade()?,
+        };
+
+        let local_offset = database.read_i16::<LittleEndian>()?;
+        let stack_leniency = database.read_f32::<LittleEndian>()?;
+        let mode = Mode::try_from(database.read_u8()?)?;
+        let source = database.read_uleb128_string()?;
+        let tags = database.read_uleb128_string()?;
+        let online_offset = database.read_i16::<LittleEndian>()?;
+        let title_font = database.read_uleb128_string()?;
+        let unplayed = database.read_bool()?;
+        let last_played = database.read_ticks()?;
+        let is_osz2 = database.read_bool()?;
+        let folder_name = database.read_uleb128_string()?;
+        let last_checked = database.read_ticks()?;
+        let ignore_beatmap_sound = database.read_bool()?;
+        let ignore_beatmap_skin = database.read_bool()?;
+        let disable_storyboard = database.read_bool()?;
+        let disable_video = database.read_bool()?;
+        let visual_override = database.read_bool()?;
+        if version < FLOAT_DIFFICULTY_VERSION
+        {
+            database.read_u16::<LittleEndian>()?;
+        }
+        // Last modification time, duplicate of `last_modified`
+        database.read_u32::<LittleEndian>()?;
+        let mania_scroll_speed = database.read_u8()?;
+
+        Ok(DatabaseBeatmap {
+            artist,
+            artist_unicode,
+            title,
+            title_unicode,
+            creator,
+            diff_name,
+            audio_filename,
+            hash,
+            filename,
+            ranked_status,
+            circles,
+            sliders,
+            spinners,
+            last_modified,
+            ar,
+            cs,
+            hp,
+            od,
+            slider_multiplier,
+            star_ratings,
+            drain_time,
+            total_time,
+            preview_time,
+            timing_points,
+            beatmap_id,
+            beatmapset_id,
+            thread_id,
+            grades,
+            local_offset,
+            stack_leniency,
+            mode,
+            source,
+            tags,
+            online_offset,
+            title_font,
+            unplayed,
+            last_played,
+            is_osz2,
+            folder_name,
+            last_checked,
+            ignore_beatmap_sound,
+            ignore_beatmap_skin,
+            disable_storyboard,
+            disable_video,
+            visual_override,
+            mania_scroll_speed,
+        })
+    }
+}
+
+/// Reads a list of `0x08 mods 0x0d stars` pairs, stars are `0x0c` floats in newer versions.
+fn read_star_ratings<R: Read>(database: &mut R, version: u32) -> ParserResult<Vec<StarRating>>
+{
+    let count = database.read_u32::<LittleEndian>()?;
+    (0..count)
+        .map(|_| {
+            database.read_u8()?;
+            let mods = database.read_u32::<LittleEndian>()?;
+            database.read_u8()?;
+            let stars = if version >= FLOAT_STAR_RATING_VERSION
+            {
+                database.read_f32::<LittleEndian>()? as f64
+            }
+            else
+            {
+                database.read_f64::<LittleEndian>()?
+            };
+            Ok(StarRating { mods, stars })
+        })
+        .collect()
+}
@@ -0,0 +1,65 @@
+use std::io::Read;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::replay::{parse::ULEB128Decode, ParserResult, Replay};
+
+/// Local scores of a beatmap.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BeatmapScores
+{
+    pub beatmap_hash: String,
+    /// Score entries, parsed like a replay header without replay data.
+    pub scores:       Vec<Replay>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ScoresDatabase
+{
+    pub version:  u32,
+    pub beatmaps: Vec<BeatmapScores>,
+}
+
+impl ScoresDatabase
+{
+    pub fn parse<R: Read>(database: &mut R) -> ParserResult<Self>
+    {
+        let version = database.read_u32::<LittleEndian>()?;
+        let beatmap_count = database.read_u32::<LittleEndian>()?;
+        let beatmaps = (0..beatmap_count)
+            .map(|_| {
+                let beatmap_hash = database.read_uleb128_string()?;
+                let score_count = database.read_u32::<LittleEndian>()?;
+                let scores = (0..score_count)
+                    .map(|_| Replay::parse(database, false))
+                    .collect::<ParserResult<Vec<_>>>()?;
+                Ok(BeatmapScores {
+                    beatmap_hash,
+                    scores,
+                })
+            })
+            .collect::<ParserResult<Vec<_>>>()?;
+
+        Ok(ScoresDatabase { version, beatmaps })
+    }
+
+    pub fn scores(&self, beatmap_hash: &str) -> &[Replay]
+    {
+        self.beatmaps
+            .iter()
+            .find(|beatmap| beatmap.beatmap_hash == beatmap_hash)
+            .map_or(&[], |beatmap| beatmap.scores.as_slice())
+    }
+
+    /// Score with the highest total score on the beatmap.
+    pub fn best_score(&self, beatmap_hash: &str) -> Option<&Replay>
+    {
+        self.scores(beatmap_hash)
+            .iter()
+            .max_by_key(|score| score.score)
+    }
+}
@@ -1,14 +1,35 @@
 //! wasm osu replay and beatmap parser
 pub mod beatmap;
+pub mod database;
 pub mod macros;
 pub mod replay;
+pub mod skin;
 
 mod utils;
 
 use beatmap::{ParserBeatmap, ParserBeatmapAttributes, ParserStrains};
 use wasm_bindgen::prelude::*;
 
-use crate::{beatmap::ParserScoreState, replay::Replay};
+use std::convert::TryFrom;
+
+use crate::{
+    beatmap::{
+        beatmapset::ParserBeatmapset, mods::RateChangedBeatmap, slider::ParserSliders,
+        stacking::ParserStackedObjects, timing::ParserTimingRanges, ParserScoreState,
+    },
+    database::{
+        collection::CollectionDatabase,
+        osu::{DatabaseCheck, OsuDatabase},
+        scores::ScoresDatabase,
+    },
+    replay::{
+        analysis::ReplayAnalysis, grade::ScoreGrade, hit_errors::ReplayHitErrors,
+        judgement::ReplayJudgements, life_graph::ReplayLifeGraph, score::ScoreCalculation,
+        similarity::ReplaySimilarity, stats::ReplayStats, timewarp::TimewarpReport, Judgements,
+        Mode, Mods, ParserError, Replay,
+    },
+    skin::ParserSkin,
+};
 
 #[wasm_bindgen]
 extern "C" {
@@ -53,6 +74,228 @@ pub fn parse_replay_extra(replay: &mut [u8], beatmap: &mut [u8]) -> Result<Repla
     Ok(extras)
 }
 
+/// Simulates osu!standard judgements of the provided replay against the provided beatmap.
+///
+/// Returns the result, hit offset and hitting frame of every hit object.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const replaydata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseReplayJudgements(replaydata, beatmapdata));
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseReplayJudgements)]
+pub fn parse_replay_judgements(
+    replay: &mut [u8],
+    beatmap: &mut [u8],
+) -> Result<ReplayJudgements, JsError> {
+    let (replay, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref())?;
+    Ok(replay.simulate_judgements(&beatmap)?)
+}
+
+/// Calculates hit error statistics such as unstable rate of the provided replay against the provided beatmap.
+///
+/// Unstable rate and errors are corrected for the DT/HT clock rate.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const replaydata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseReplayHitErrors(replaydata, beatmapdata).unstable_rate);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseReplayHitErrors)]
+pub fn parse_replay_hit_errors(
+    replay: &mut [u8],
+    beatmap: &mut [u8],
+) -> Result<ReplayHitErrors, JsError> {
+    let (replay, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref())?;
+    Ok(replay.hit_errors(&beatmap)?)
+}
+
+/// Calculates ScoreV1 and ScoreV2 of the provided replay from its simulated judgements against the provided beatmap.
+///
+/// Every object is judged from the replay frames and spinner bonus is included, so this is the score
+/// to compare when lobbies desync. Only the order of missed slider ticks is assumed.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const replaydata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseReplayScore(replaydata, beatmapdata).score_v2);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseReplayScore)]
+pub fn parse_replay_score(
+    replay: &mut [u8],
+    beatmap: &mut [u8],
+) -> Result<ScoreCalculation, JsError> {
+    let (replay, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref())?;
+    Ok(replay.calculate_score(&beatmap)?)
+}
+
+/// Reconstructs the osu!standard health of the provided replay from its simulated judgements against the provided beatmap.
+///
+/// Health drains like in osu! stable. Unlike the stored life graph, health is sampled every 50ms and the exact fail time is reported.
+/// # Example
+///
+/// ```
+///  const life = wasm.parseReplayLifeGraph(replaydata, beatmapdata);
+///  if (!life.passed) {
+///    console.log(`failed at ${life.fail_time}ms`);
+///  }
+/// ```
+#[wasm_bindgen(js_name = parseReplayLifeGraph)]
+pub fn parse_replay_life_graph(
+    replay: &mut [u8],
+    beatmap: &mut [u8],
+) -> Result<ReplayLifeGraph, JsError> {
+    let (replay, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref())?;
+    Ok(replay.reconstruct_life_graph(&beatmap)?)
+}
+
+/// Runs relax and aim assist heuristics on the provided replay against the provided beatmap.
+///
+/// Every heuristic has a score between 0 and 1 and the frame ranges responsible for it.
+/// # Example
+///
+/// ```
+///  const analysis = wasm.parseReplayAnalysis(replaydata, beatmapdata);
+///  if (analysis.suspicion > 0.5) {
+///    console.log(analysis.cursor_snapping.frames);
+///  }
+/// ```
+#[wasm_bindgen(js_name = parseReplayAnalysis)]
+pub fn parse_replay_analysis(
+    replay: &mut [u8],
+    beatmap: &mut [u8],
+) -> Result<ReplayAnalysis, JsError> {
+    let (replay, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref())?;
+    Ok(replay.analyze(&beatmap)?)
+}
+
+/// Calculates the accuracy and grade of the provided replay from its stored judgements.
+/// # Example
+///
+/// ```
+///  const { accuracy, grade } = wasm.parseReplayGrade(replaydata);
+///  console.log(`${grade} ${(accuracy * 100).toFixed(2)}%`);
+/// ```
+#[wasm_bindgen(js_name = parseReplayGrade)]
+pub fn parse_replay_grade(replay: &mut [u8]) -> Result<ScoreGrade, JsError> {
+    let parsed = Replay::parse(&mut replay.as_ref(), false)?;
+    Ok(parsed.score_grade())
+}
+
+/// Calculates the accuracy and grade of the provided judgements in the provided mode.
+///
+/// HD, FL and FadeIn turn SS and S grades into their silver variants.
+/// # Example
+///
+/// ```
+///  const judgements = { count_300: 800, count_100: 12, count_50: 0, count_geki: 200, count_katu: 5, miss: 1 };
+///  console.log(wasm.calculateGrade(judgements, 3, 8).grade);
+/// ```
+#[wasm_bindgen(js_name = calculateGrade)]
+pub fn calculate_grade(judgements: Judgements, mode: u8, mods: u32) -> Result<ScoreGrade, JsError> {
+    let mode = Mode::try_from(mode)?;
+    let mods = Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?;
+    Ok(judgements.score_grade(mode, mods))
+}
+
+/// Calculates key press and cursor statistics of the provided replay.
+///
+/// Durations, tapping BPM and cursor speed are corrected for the DT/HT clock rate.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const replaydata = new Uint8Array(evt.target.result);
+///    const stats = wasm.parseReplayStats(replaydata);
+///    console.log(stats.total_presses, stats.cursor_distance);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseReplayStats)]
+pub fn parse_replay_stats(replay: &mut [u8]) -> Result<ReplayStats, JsError> {
+    let mut parsed = Replay::parse(&mut replay.as_ref(), true)?;
+    parsed.decode_frames()?;
+    Ok(parsed.stats()?)
+}
+
+/// Compares the frame times of the provided replay with the clock rate of its mods.
+///
+/// A suspicion score above 0 means the replay was recorded at a different game speed than its
+/// mods allow, which is the case for timewarped replays.
+/// # Example
+///
+/// ```
+///  const report = wasm.parseReplayTimewarp(replaydata);
+///  if (report.suspicion > 0.5) {
+///    console.log(report.apparent_clock_rate, report.evidence);
+///  }
+/// ```
+#[wasm_bindgen(js_name = parseReplayTimewarp)]
+pub fn parse_replay_timewarp(replay: &mut [u8]) -> Result<TimewarpReport, JsError> {
+    let mut parsed = Replay::parse(&mut replay.as_ref(), true)?;
+    parsed.decode_frames()?;
+    Ok(parsed.timewarp()?)
+}
+
+/// Compares the cursor movement and key presses of two replays of the same beatmap.
+///
+/// With `correct_flip`, the second replay is flipped if only one of them was played with HR.
+/// # Example
+///
+/// ```
+///  const similarity = wasm.compareReplays(replaydata, otherreplaydata, true);
+///  if (similarity.mean_distance < 20) {
+///    console.log("possibly copied", similarity);
+///  }
+/// ```
+#[wasm_bindgen(js_name = compareReplays)]
+pub fn compare_replays(
+    replay: &mut [u8],
+    other: &mut [u8],
+    correct_flip: bool,
+) -> Result<ReplaySimilarity, JsError> {
+    let mut parsed = Replay::parse(&mut replay.as_ref(), true)?;
+    parsed.decode_frames()?;
+    let mut other = Replay::parse(&mut other.as_ref(), true)?;
+    other.decode_frames()?;
+    Ok(parsed.similarity(&other, correct_flip)?)
+}
+
+/// Serializes the provided replay back into the .osr format.
+///
+/// Frame data is re-encoded when present, otherwise the raw replay data is written back as-is.
+/// # Example
+///
+/// ```
+///  const replay = wasm.parseReplayExtra(replaydata, beatmapdata);
+///  replay.username = "anonymous";
+///  const osr = wasm.serializeReplay(replay);
+/// ```
+#[wasm_bindgen(js_name = serializeReplay)]
+pub fn serialize_replay(replay: Replay) -> Result<Vec<u8>, JsError> {
+    let mut out = Vec::new();
+    replay.write(&mut out)?;
+    Ok(out)
+}
+
 #[derive(Copy, Clone)]
 #[wasm_bindgen]
 pub struct ParserScore {
@@ -80,9 +323,165 @@ pub fn parse_beatmap(beatmap: &mut [u8]) -> Result<ParserBeatmap, JsError> {
     let parsed = ParserBeatmap::parse(&mut beatmap.as_ref())?;
     Ok(parsed)
 }
+/// Parses every difficulty of the provided .osz beatmapset and lists the files it contains.
+///
+/// Audio, background, video, storyboard and hitsound files are listed with their size and MD5 hash.
+/// Difficulties which fail to parse are listed in `failed_beatmaps` with their error. Archives with a
+/// file larger than 256 MiB or more than 512 MiB of files are rejected.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const oszdata = new Uint8Array(evt.target.result);
+///    const beatmapset = wasm.parseBeatmapset(oszdata);
+///    console.log(beatmapset.beatmaps.map((beatmap) => beatmap.diff_name), beatmapset.files);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseBeatmapset)]
+pub fn parse_beatmapset(osz: &mut [u8]) -> Result<ParserBeatmapset, JsError> {
+    Ok(ParserBeatmapset::parse(std::io::Cursor::new(osz))?)
+}
+/// Parses the provided beatmap with the effect of the provided mods applied.
+///
+/// AR, OD, CS, HP, BPM, map length and drain time are adjusted for HR/EZ and the DT/HT clock rate.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const beatmapdata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseBeatmapWithMods(beatmapdata, 64 | 16));
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseBeatmapWithMods)]
+pub fn parse_beatmap_with_mods(beatmap: &mut [u8], mods: u32) -> Result<ParserBeatmap, JsError> {
+    let parsed = ParserBeatmap::parse_with_mods(&mut beatmap.as_ref(), mods)?;
+    Ok(parsed)
+}
+/// Parses the provided beatmap and applies every effect of the provided mods.
+///
+/// HR and Mirror flip the hit objects and DT/NC/HT scale the times of objects, timing points and breaks.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const beatmapdata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseBeatmapTransformed(beatmapdata, 64 | 16).hit_objects);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseBeatmapTransformed)]
+pub fn parse_beatmap_transformed(beatmap: &mut [u8], mods: u32) -> Result<ParserBeatmap, JsError> {
+    let mods = Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?;
+    let parsed = ParserBeatmap::parse(&mut beatmap.as_ref())?;
+    Ok(parsed.transformed(mods))
+}
+/// Serializes the provided beatmap in the osu file format v14.
+/// # Example
+///
+/// ```
+///  const beatmap = wasm.parseBeatmapTransformed(beatmapdata, 16);
+///  beatmap.diff_name += " (HR)";
+///  const osu = wasm.serializeBeatmap(beatmap);
+/// ```
+#[wasm_bindgen(js_name = serializeBeatmap)]
+pub fn serialize_beatmap(beatmap: ParserBeatmap) -> Result<Vec<u8>, JsError> {
+    let mut out = Vec::new();
+    beatmap.write(&mut out)?;
+    Ok(out)
+}
+/// Generates the .osu file of the provided beatmap at another rate, with its new MD5 hash.
+///
+/// Times, BPM, preview time, breaks, storyboard and video are scaled, and AR/OD are adjusted to play the same as a DT-style rate change.
+/// AR and OD outside of 0 to 10 are clamped and returned as `uncapped_ar` and `uncapped_od`, and the beatmap ID is set to 0.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const beatmapdata = new Uint8Array(evt.target.result);
+///    const { osu, hash, uncapped_ar } = wasm.generateRateChangedBeatmap(beatmapdata, 1.2);
+/// }
+/// ```
+#[wasm_bindgen(js_name = generateRateChangedBeatmap)]
+pub fn generate_rate_changed_beatmap(
+    beatmap: &mut [u8],
+    rate: f64,
+) -> Result<RateChangedBeatmap, JsError> {
+    let generated = ParserBeatmap::generate_rate_changed(&mut beatmap.as_ref(), rate)?;
+    Ok(generated)
+}
+/// Parses the provided beatmap and evaluates the path, end time, ticks and repeats of every slider.
+///
+/// If a sample distance is provided, slider paths are resampled to points that many osu!pixels apart.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const beatmapdata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseBeatmapSliders(beatmapdata, 8).sliders);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseBeatmapSliders)]
+pub fn parse_beatmap_sliders(
+    beatmap: &mut [u8],
+    sample_distance: Option<f64>,
+) -> Result<ParserSliders, JsError> {
+    let parsed = ParserBeatmap::parse(&mut beatmap.as_ref())?;
+    Ok(parsed.sliders(sample_distance))
+}
+/// Parses the provided beatmap and derives its kiai sections and slider velocity ranges from the timing points.
+///
+/// The last kiai section and slider velocity range end at the end of the beatmap.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const beatmapdata = new Uint8Array(evt.target.result);
+///    const ranges = wasm.parseBeatmapTimingRanges(beatmapdata);
+///    console.log(ranges.kiai_intervals, ranges.slider_velocity_ranges);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseBeatmapTimingRanges)]
+pub fn parse_beatmap_timing_ranges(beatmap: &mut [u8]) -> Result<ParserTimingRanges, JsError> {
+    let parsed = ParserBeatmap::parse(&mut beatmap.as_ref())?;
+    Ok(parsed.timing_ranges())
+}
+/// Parses the provided beatmap and calculates the stack height and stacked position of every hit object.
+///
+/// With HR and Mirror the objects are flipped before they are stacked.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const beatmapdata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseBeatmapStacking(beatmapdata, 16).objects);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseBeatmapStacking)]
+pub fn parse_beatmap_stacking(
+    beatmap: &mut [u8],
+    mods: u32,
+) -> Result<ParserStackedObjects, JsError> {
+    let mods = Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?;
+    let parsed = ParserBeatmap::parse(&mut beatmap.as_ref())?;
+    Ok(parsed.stacking(mods))
+}
 /// Parses the provided beatmap and calculates difficulty and performance attributes.
 ///
 /// If a [`ParserScore`] is provided, it will use the provided score state for calculation.
+/// If a mode is provided, osu!standard beatmaps are converted to it, otherwise the beatmap's own mode is used.
 /// # Example
 ///
 /// ```
@@ -90,22 +489,56 @@ pub fn parse_beatmap(beatmap: &mut [u8]) -> Result<ParserBeatmap, JsError> {
 ///
 ///  reader.onloadend = (evt) => {
 ///    const beatmapdata = new Uint8Array(evt.target.result);
-///    console.log(wasm.parseBeatmapAttributes(beatmapdata));
+///    console.log(wasm.parseBeatmapAttributes(undefined, beatmapdata, 1).mode_performance);
 /// }
 /// ```
 #[wasm_bindgen(js_name = parseBeatmapAttributes)]
 pub fn parse_beatmap_attributes(
     score: Option<ParserScore>,
     beatmap: &mut [u8],
+    mode: Option<u8>,
 ) -> Result<ParserBeatmapAttributes, JsError> {
     Ok(ParserBeatmap::parse_beatmap_attributes(
         score,
         &mut beatmap.as_ref(),
+        mode,
+    )?)
+}
+/// Estimates ScoreV1 and ScoreV2 of the provided score on the provided osu!standard beatmap.
+///
+/// Uses the judgements, combo and mods of the score. Both scores depend on the combo at every hit,
+/// so they are estimated from streaks of the max combo, only the ScoreV2 accuracy portion is exact.
+/// Spinner bonus is left out and sliders are assumed to be hit in proportion to the objects.
+///
+/// The estimate cannot settle a desync or a tie between scores. Recompute those from the replays
+/// with `parseReplayScore`, which judges every object and includes spinner bonus.
+/// # Example
+///
+/// ```
+///  const score = new wasm.ParserScore();
+///  score.mods = 16;
+///  score.combo = 1200;
+///  score.judgements = { count_300: 800, count_100: 12, count_50: 0, miss: 1 };
+///  console.log(wasm.calculateScore(score, beatmapdata).score_v2);
+/// ```
+#[wasm_bindgen(js_name = calculateScore)]
+pub fn calculate_score(
+    score: ParserScore,
+    beatmap: &mut [u8],
+) -> Result<ScoreCalculation, JsError> {
+    let mods = score.mods.unwrap_or_default();
+    let mods = Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?;
+    let parsed = ParserBeatmap::parse(&mut beatmap.as_ref())?;
+    Ok(score.judgements.unwrap_or_default().score(
+        score.combo.unwrap_or_default() as u32,
+        &parsed,
+        mods,
     )?)
 }
 /// Parses the provided beatmap and calculates an array of gradual difficulty and optionally performance strains.
 ///
 /// If an array of [`ParserScoreState`] is provided, it will also calculate gradual performance strains.
+/// Like [`parse_beatmap_attributes`], osu!standard beatmaps are converted to the provided mode.
 /// # Example
 ///
 /// ```
@@ -121,6 +554,7 @@ pub fn parse_beatmap_strains(
     beatmap: &mut [u8],
     score_states: Option<Vec<JsValue>>,
     mods: Option<u32>,
+    mode: Option<u8>,
 ) -> Result<ParserStrains, JsError> {
     Ok(ParserBeatmap::parse_beatmap_strains(
         &mut beatmap.as_ref(),
@@ -135,8 +569,153 @@ pub fn parse_beatmap_strains(
             None => None,
         },
         mods,
+        mode,
     )?)
 }
+/// Parses the skin.ini and element images of the provided .osk skin.
+///
+/// Reports gameplay elements which are missing or larger than their recommended size. Skins with a
+/// file larger than 256 MiB or more than 512 MiB of files are rejected.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const oskdata = new Uint8Array(evt.target.result);
+///    const skin = wasm.parseSkin(oskdata);
+///    console.log(skin.ini?.general.name, skin.missing_elements, skin.oversized_elements);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseSkin)]
+pub fn parse_skin(osk: &mut [u8]) -> Result<ParserSkin, JsError> {
+    Ok(ParserSkin::parse(std::io::Cursor::new(osk))?)
+}
+/// Parses the provided osu!.db.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const databasedata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseOsuDatabase(databasedata).beatmaps.length);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseOsuDatabase)]
+pub fn parse_osu_database(database: &mut [u8]) -> Result<OsuDatabase, JsError> {
+    Ok(OsuDatabase::parse(&mut database.as_ref())?)
+}
+/// Checks which of the provided beatmaps are in the provided osu!.db and whether they are up to date.
+///
+/// Beatmaps are compared by hash, see [`OsuDatabase::check_beatmap`].
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const databasedata = new Uint8Array(evt.target.result);
+///    const pool = beatmaps.map((beatmapdata) => wasm.parseBeatmap(beatmapdata));
+///    console.log(wasm.checkOsuDatabase(databasedata, pool));
+/// }
+/// ```
+#[wasm_bindgen(js_name = checkOsuDatabase)]
+pub fn check_osu_database(
+    database: &mut [u8],
+    beatmaps: Vec<JsValue>,
+) -> Result<DatabaseCheck, JsError> {
+    let database = OsuDatabase::parse(&mut database.as_ref())?;
+    let beatmaps = beatmaps
+        .into_iter()
+        .map(serde_wasm_bindgen::from_value)
+        .collect::<Result<Vec<ParserBeatmap>, _>>()?;
+    Ok(database.check_beatmaps(&beatmaps))
+}
+/// Parses the provided collection.db.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const collectiondata = new Uint8Array(evt.target.result);
+///    console.log(wasm.parseCollectionDatabase(collectiondata).collections);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseCollectionDatabase)]
+pub fn parse_collection_database(database: &mut [u8]) -> Result<CollectionDatabase, JsError> {
+    Ok(CollectionDatabase::parse(&mut database.as_ref())?)
+}
+/// Serializes the provided collections into the collection.db format.
+/// # Example
+///
+/// ```
+///  const collections = wasm.parseCollectionDatabase(collectiondata);
+///  collections.collections = collections.collections.filter((x) => x.name !== "old pool");
+///  const db = wasm.serializeCollectionDatabase(collections);
+/// ```
+#[wasm_bindgen(js_name = serializeCollectionDatabase)]
+pub fn serialize_collection_database(database: CollectionDatabase) -> Result<Vec<u8>, JsError> {
+    let mut out = Vec::new();
+    database.write(&mut out)?;
+    Ok(out)
+}
+/// Generates a collection.db containing a single collection of the provided beatmap hashes.
+///
+/// If an existing collection.db is provided, the collection is merged into it instead.
+/// # Example
+///
+/// ```
+///  const hashes = beatmaps.map((beatmapdata) => wasm.parseBeatmap(beatmapdata).hash);
+///  const db = wasm.generateCollectionDatabase("Corsace Open Qualifiers", hashes, existingdata);
+/// ```
+#[wasm_bindgen(js_name = generateCollectionDatabase)]
+pub fn generate_collection_database(
+    name: &str,
+    hashes: Vec<String>,
+    existing: Option<Vec<u8>>,
+) -> Result<Vec<u8>, JsError> {
+    let collection = CollectionDatabase::from_hashes(name, hashes);
+    let database = match existing {
+        Some(existing) => {
+            let mut database = CollectionDatabase::parse(&mut existing.as_slice())?;
+            database.merge(collection);
+            database
+        }
+        None => collection,
+    };
+    let mut out = Vec::new();
+    database.write(&mut out)?;
+    Ok(out)
+}
+/// Parses the provided scores.db, every score is returned as a [`Replay`] without replay data.
+///
+/// If beatmap hashes are provided, only the scores of those beatmaps are returned.
+/// # Example
+///
+/// ```
+///  const reader = new FileReader();
+///
+///  reader.onloadend = (evt) => {
+///    const scoresdata = new Uint8Array(evt.target.result);
+///    const hashes = beatmaps.map((beatmapdata) => wasm.parseBeatmap(beatmapdata).hash);
+///    console.log(wasm.parseScoresDatabase(scoresdata, hashes).beatmaps);
+/// }
+/// ```
+#[wasm_bindgen(js_name = parseScoresDatabase)]
+pub fn parse_scores_database(
+    database: &mut [u8],
+    beatmap_hashes: Option<Vec<String>>,
+) -> Result<ScoresDatabase, JsError> {
+    let mut parsed = ScoresDatabase::parse(&mut database.as_ref())?;
+    if let Some(hashes) = beatmap_hashes {
+        parsed
+            .beatmaps
+            .retain(|beatmap| hashes.contains(&beatmap.beatmap_hash));
+    }
+    Ok(parsed)
+}
 /// Call on init for better panic reports when debugging, not required.
 #[wasm_bindgen]
 pub fn init_panic_hook() {
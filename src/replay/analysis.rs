@@ -0,0 +1,328 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    judgement::{circle_radius, ReplayJudgements},
+    Buttons, Mods, ParserError, ParserResult, Replay, ReplayFrameData,
+};
+use crate::beatmap::{mods::scale_difficulty, objects::HitObjectKind, ParserBeatmap};
+
+/// Hits closer to the object time than this many ms are aligned with it.
+const ALIGNED_OFFSET: f64 = 2.0;
+/// Share of aligned hits which is not suspicious.
+const ALIGNED_BASELINE: f64 = 0.2;
+/// Share of aligned hits which is fully suspicious.
+const ALIGNED_MAX: f64 = 0.6;
+/// Unstable rate above which hit errors are not suspicious.
+const UNSTABLE_RATE_BASELINE: f64 = 50.0;
+/// Unstable rate below which hit errors are fully suspicious.
+const UNSTABLE_RATE_MIN: f64 = 25.0;
+/// Share of presses hitting an object which is not suspicious.
+const PRESSES_BASELINE: f64 = 0.95;
+/// Minimum distance in osu!pixels a cursor has to jump for a snap.
+const SNAP_MIN_DISTANCE: f32 = 20.0;
+/// How many times longer than the previous movement a snapping jump is.
+const SNAP_RATIO: f32 = 4.0;
+/// Distance to the object centre a snap ends within, relative to the circle radius.
+const SNAP_CENTRE_DISTANCE: f32 = 0.1;
+/// Share of snapping hits which is not suspicious.
+const SNAP_BASELINE: f64 = 0.02;
+/// Share of snapping hits which is fully suspicious.
+const SNAP_MAX: f64 = 0.2;
+/// Cursor speed in osu!pixels per ms of real time no player reaches.
+const MAX_CURSOR_SPEED: f64 = 30.0;
+/// Frames faster than [`MAX_CURSOR_SPEED`] which are fully suspicious.
+const MAX_FAST_FRAMES: f64 = 10.0;
+
+/// Frames of [`ReplayFrameData::frames`] between two indices, both inclusive.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FrameRange
+{
+    pub start_frame: usize,
+    pub end_frame:   usize,
+    pub start_time:  i32,
+    pub end_time:    i32,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct HeuristicResult
+{
+    /// Between 0 and 1, where 0 means the replay looks human.
+    pub score:  f64,
+    /// Value the score is based on, see [`ReplayAnalysis`].
+    pub value:  f64,
+    /// Frames responsible for the score, empty for heuristics over the whole replay.
+    pub frames: Vec<FrameRange>,
+}
+
+/// Relax and aim assist heuristics of a replay.
+///
+/// Scores are independent of each other and meant to rank replays for review, a high score alone
+/// does not prove cheating.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplayAnalysis
+{
+    /// Share of hits within 2ms of the object time.
+    pub aligned_presses:    HeuristicResult,
+    /// Unstable rate of the hits.
+    pub hit_error_variance: HeuristicResult,
+    /// Share of presses outside of spinners which hit an object.
+    ///
+    /// Not part of `suspicion`, since clean plays also hit an object with almost every press.
+    pub presses_on_objects: HeuristicResult,
+    /// Share of hits where the cursor jumped onto the object centre.
+    pub cursor_snapping:    HeuristicResult,
+    /// Number of frames with a cursor speed no player reaches.
+    pub cursor_velocity:    HeuristicResult,
+    /// Highest score of all heuristics other than `presses_on_objects`.
+    pub suspicion:          f64,
+}
+
+impl ReplayFrameData
+{
+    /// Runs every heuristic on the frames and their judgements, see
+    /// [`Replay::simulate_judgements`].
+    pub fn analyze(
+        &self, judgements: &ReplayJudgements, beatmap: &ParserBeatmap, mods: Mods,
+    ) -> ReplayAnalysis
+    {
+        let mut analysis = ReplayAnalysis {
+            aligned_presses:    self.aligned_presses(judgements, mods),
+            hit_error_variance: hit_error_variance(judgements, mods),
+            presses_on_objects: self.presses_on_objects(judgements, beatmap),
+            cursor_snapping:    self.cursor_snapping(judgements, beatmap, mods),
+            cursor_velocity:    self.cursor_velocity(mods),
+            suspicion:          0.0,
+        };
+        analysis.suspicion = [
+            &analysis.aligned_presses,
+            &analysis.hit_error_variance,
+            &analysis.cursor_snapping,
+            &analysis.cursor_velocity,
+        ]
+        .iter()
+        .map(|heuristic| heuristic.score)
+        .fold(0.0, f64::max);
+        analysis
+    }
+
+    fn aligned_presses(&self, judgements: &ReplayJudgements, mods: Mods) -> HeuristicResult
+    {
+        let clock_rate = mods.clock_rate();
+        let hits = judgements
+            .objects
+            .iter()
+            .filter_map(|object| Some((object.hit_offset?, object.frame_index?)))
+            .collect::<Vec<_>>();
+        let aligned = hits
+            .iter()
+            .filter(|(offset, _)| (offset / clock_rate).abs() <= ALIGNED_OFFSET)
+            .map(|(_, frame_index)| *frame_index)
+            .collect::<Vec<_>>();
+        let value = share(aligned.len(), hits.len());
+
+        HeuristicResult {
+            score: scale(value, ALIGNED_BASELINE, ALIGNED_MAX),
+            value,
+            frames: self.frame_ranges(aligned.iter().map(|index| (*index, *index))),
+        }
+    }
+
+    fn presses_on_objects(
+        &self, judgements: &ReplayJudgements, beatmap: &ParserBeatmap,
+    ) -> HeuristicResult
+    {
+        let spinners = beatmap
+            .hit_objects
+            .iter()
+            .flatten()
+            .filter_map(|object| match object.kind
+            {
+                HitObjectKind::Spinner { end_time } => Some((object.start_time, end_time)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let range = self.gameplay_range();
+        let mut presses = 0;
+        for pair in self.frames[range.start.saturating_sub(1)..range.end].windows(2)
+        {
+            let time = pair[1].timestamp_ms as f64;
+            if spinners
+                .iter()
+                .any(|(start, end)| time >= *start && time <= *end)
+            {
+                continue;
+            }
+            let pressed = pair[1].buttons & !pair[0].buttons & (Buttons::M1 | Buttons::M2);
+            presses += pressed.bits().count_ones() as usize;
+        }
+        let on_objects = judgements
+            .objects
+            .iter()
+            .filter(|object| object.frame_index.is_some())
+            .count();
+        let value = share(on_objects, presses).min(1.0);
+
+        HeuristicResult {
+            score: scale(value, PRESSES_BASELINE, 1.0),
+            value,
+            frames: Vec::new(),
+        }
+    }
+
+    fn cursor_snapping(
+        &self, judgements: &ReplayJudgements, beatmap: &ParserBeatmap, mods: Mods,
+    ) -> HeuristicResult
+    {
+        let stacked = beatmap.stacking(mods).objects;
+        let max_distance =
+            circle_radius(scale_difficulty(beatmap.cs, mods, 1.3)) * SNAP_CENTRE_DISTANCE;
+        let range = self.gameplay_range();
+
+        let mut hits = 0;
+        let mut snaps = Vec::new();
+        for object in &judgements.objects
+        {
+            let (frame_index, centre) = match (object.frame_index, stacked.get(object.object_index))
+            {
+                (Some(frame_index), Some(stacked)) => (frame_index, stacked.stacked_position),
+                _ => continue,
+            };
+            hits += 1;
+            // The jump can happen on the pressing frame or right before it
+            let snap = (frame_index.saturating_sub(1)..=frame_index)
+                .filter(|index| *index >= range.start + 2)
+                .find(|index| {
+                    let [before, previous, current] = [
+                        self.frames[index - 2].cursor_pos,
+                        self.frames[index - 1].cursor_pos,
+                        self.frames[*index].cursor_pos,
+                    ];
+                    let jump = previous.distance(current);
+                    jump >= SNAP_MIN_DISTANCE
+                        && jump > before.distance(previous) * SNAP_RATIO
+                        && current.distance(centre) <= max_distance
+                });
+            if let Some(index) = snap
+            {
+                snaps.push((index - 1, index));
+            }
+        }
+        let value = share(snaps.len(), hits);
+
+        HeuristicResult {
+            score: scale(value, SNAP_BASELINE, SNAP_MAX),
+            value,
+            frames: self.frame_ranges(snaps.into_iter()),
+        }
+    }
+
+    fn cursor_velocity(&self, mods: Mods) -> HeuristicResult
+    {
+        let clock_rate = mods.clock_rate();
+        let range = self.gameplay_range();
+        let fast = (range.start + 1..range.end)
+            .filter(|index| {
+                let frame = &self.frames[*index];
+                // Frames without time passing and skips have no meaningful speed
+                if frame.time_since_ms <= 0
+                {
+                    return false;
+                }
+                let distance = frame.cursor_pos.distance(self.frames[index - 1].cursor_pos) as f64;
+                distance / (frame.time_since_ms as f64 / clock_rate) > MAX_CURSOR_SPEED
+            })
+            .map(|index| (index - 1, index))
+            .collect::<Vec<_>>();
+        let value = fast.len() as f64;
+
+        HeuristicResult {
+            score: scale(value, 0.0, MAX_FAST_FRAMES),
+            value,
+            frames: self.frame_ranges(fast.into_iter()),
+        }
+    }
+
+    /// Merges overlapping and adjacent frame index ranges.
+    fn frame_ranges(&self, ranges: impl Iterator<Item = (usize, usize)>) -> Vec<FrameRange>
+    {
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, end) in ranges
+        {
+            match merged.last_mut()
+            {
+                Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+            .into_iter()
+            .map(|(start, end)| FrameRange {
+                start_frame: start,
+                end_frame:   end,
+                start_time:  self.frames[start].timestamp_ms,
+                end_time:    self.frames[end].timestamp_ms,
+            })
+            .collect()
+    }
+}
+
+fn hit_error_variance(judgements: &ReplayJudgements, mods: Mods) -> HeuristicResult
+{
+    let hit_errors = judgements.hit_errors(mods);
+    let score = if hit_errors.hit_offsets.is_empty()
+    {
+        0.0
+    }
+    else
+    {
+        1.0 - scale(
+            hit_errors.unstable_rate,
+            UNSTABLE_RATE_MIN,
+            UNSTABLE_RATE_BASELINE,
+        )
+    };
+
+    HeuristicResult {
+        score,
+        value: hit_errors.unstable_rate,
+        frames: Vec::new(),
+    }
+}
+
+fn share(count: usize, total: usize) -> f64
+{
+    if total == 0
+    {
+        0.0
+    }
+    else
+    {
+        count as f64 / total as f64
+    }
+}
+
+/// Maps a value between `baseline` and `max` linearly to a score between 0 and 1.
+fn scale(value: f64, baseline: f64, max: f64) -> f64
+{
+    ((value - baseline) / (max - baseline)).clamp(0.0, 1.0)
+}
+
+impl Replay
+{
+    /// Relax and aim assist heuristics of the replay against the beatmap, see
+    /// [`Replay::simulate_judgements`].
+    pub fn analyze(&self, beatmap: &ParserBeatmap) -> ParserResult<ReplayAnalysis>
+    {
+        let judgements = self.simulate_judgements(beatmap)?;
+        Ok(self
+            .replay_frame_data
+            .as_ref()
+            .ok_or(ParserError::ReplayFramesMissing)?
+            .analyze(&judgements, beatmap, self.mods))
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{Grade, Judgements, Mode, Mods, Replay};
+
+/// Accuracy and letter grade of a score.
+#[derive(Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ScoreGrade
+{
+    /// Between 0 and 1.
+    pub accuracy: f64,
+    pub grade:    Grade,
+}
+
+impl Judgements
+{
+    /// Accuracy between 0 and 1 like osu! stable calculates it for the mode, 1 without any
+    /// judgements.
+    ///
+    /// Taiko ignores geki and katu, catch counts katu as missed droplets and mania weights geki as
+    /// 300 and katu as 200.
+    pub fn accuracy(&self, mode: Mode) -> f64
+    {
+        let [count_300, count_100, count_50, geki, katu, miss] = self.counts();
+        let (hits, total) = match mode
+        {
+            Mode::Osu => (
+                count_300 * 300.0 + count_100 * 100.0 + count_50 * 50.0,
+                (count_300 + count_100 + count_50 + miss) * 300.0,
+            ),
+            Mode::Taiko => (count_300 + count_100 * 0.5, count_300 + count_100 + miss),
+            Mode::Catch => (
+                count_300 + count_100 + count_50,
+                count_300 + count_100 + count_50 + katu + miss,
+            ),
+            Mode::Mania => (
+                (geki + count_300) * 300.0 + katu * 200.0 + count_100 * 100.0 + count_50 * 50.0,
+                (geki + count_300 + katu + count_100 + count_50 + miss) * 300.0,
+            ),
+        };
+        if total > 0.0
+        {
+            hits / total
+        }
+        else
+        {
+            1.0
+        }
+    }
+
+    /// Letter grade like osu! stable awards it for the mode, with silver grades for HD, FL and
+    /// FadeIn.
+    ///
+    /// osu!standard and taiko grades depend on the share of 300s and misses, catch and mania
+    /// grades on the accuracy. Failed scores are not detected.
+    pub fn grade(&self, mode: Mode, mods: Mods) -> Grade
+    {
+        let accuracy = self.accuracy(mode);
+        let grade = match mode
+        {
+            Mode::Osu | Mode::Taiko =>
+            {
+                let [count_300, count_100, count_50, _, _, miss] = self.counts();
+                let total = count_300 + count_100 + count_50 + miss;
+                let ratio_300 = if total > 0.0 { count_300 / total } else { 1.0 };
+                let ratio_50 = if total > 0.0 { count_50 / total } else { 0.0 };
+                let full_combo = miss == 0.0;
+
+                if ratio_300 == 1.0
+                {
+                    Grade::SS
+                }
+                else if ratio_300 > 0.9 && ratio_50 < 0.01 && full_combo
+                {
+                    Grade::S
+                }
+                else if (ratio_300 > 0.8 && full_combo) || ratio_300 > 0.9
+                {
+                    Grade::A
+                }
+                else if (ratio_300 > 0.7 && full_combo) || ratio_300 > 0.8
+                {
+                    Grade::B
+                }
+                else if ratio_300 > 0.6
+                {
+                    Grade::C
+                }
+                else
+                {
+                    Grade::D
+                }
+            }
+            Mode::Catch => accuracy_grade(accuracy, [0.98, 0.94, 0.9, 0.85]),
+            Mode::Mania => accuracy_grade(accuracy, [0.95, 0.9, 0.8, 0.7]),
+        };
+
+        let silver = mods.intersects(Mods::Hidden | Mods::Flashlight | Mods::FadeIn);
+        match grade
+        {
+            Grade::SS if silver => Grade::SSH,
+            Grade::S if silver => Grade::SH,
+            grade => grade,
+        }
+    }
+
+    pub fn score_grade(&self, mode: Mode, mods: Mods) -> ScoreGrade
+    {
+        ScoreGrade {
+            accuracy: self.accuracy(mode),
+            grade:    self.grade(mode, mods),
+        }
+    }
+
+    /// 300, 100, 50, geki, katu and miss counts, missing ones are 0.
+    fn counts(&self) -> [f64; 6]
+    {
+        [
+            self.count_300,
+            self.count_100,
+            self.count_50,
+            self.count_geki,
+            self.count_katu,
+            self.miss,
+        ]
+        .map(|count| count.unwrap_or(0) as f64)
+    }
+}
+
+/// Grade from the minimum accuracies of S, A, B and C, anything below is a D.
+fn accuracy_grade(accuracy: f64, thresholds: [f64; 4]) -> Grade
+{
+    if accuracy == 1.0
+    {
+        return Grade::SS;
+    }
+    let grades = [Grade::S, Grade::A, Grade::B, Grade::C];
+    grades
+        .iter()
+        .zip(thresholds.iter())
+        .find(|(_, threshold)| accuracy > **threshold)
+        .map_or(Grade::D, |(grade, _)| *grade)
+}
+
+impl Replay
+{
+    /// Accuracy and grade of the stored judgements.
+    pub fn score_grade(&self) -> ScoreGrade { self.judgements.score_grade(self.mode, self.mods) }
+}
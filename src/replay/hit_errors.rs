@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{judgement::ReplayJudgements, Mods, ParserResult, Replay};
+use crate::beatmap::ParserBeatmap;
+
+const HISTOGRAM_BIN_SIZE: f64 = 5.0;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct HitErrorBin
+{
+    /// Lower bound of the bin in ms.
+    pub offset: f64,
+    pub count:  u32,
+}
+
+/// Hit error statistics of a replay.
+///
+/// `hit_offsets` are in beatmap time like [`super::judgement::ObjectJudgement::hit_offset`],
+/// every other value is in real time, meaning it is corrected for the DT/HT clock rate.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplayHitErrors
+{
+    pub hit_offsets:   Vec<f64>,
+    pub mean_error:    f64,
+    pub unstable_rate: f64,
+    pub early_count:   u32,
+    pub late_count:    u32,
+    pub early_mean:    f64,
+    pub late_mean:     f64,
+    pub histogram:     Vec<HitErrorBin>,
+}
+
+impl ReplayJudgements
+{
+    pub fn hit_errors(&self, mods: Mods) -> ReplayHitErrors
+    {
+        let hit_offsets = self
+            .objects
+            .iter()
+            .filter_map(|object| object.hit_offset)
+            .collect::<Vec<_>>();
+        if hit_offsets.is_empty()
+        {
+            return ReplayHitErrors::default();
+        }
+
+        let clock_rate = mods.clock_rate();
+        let errors = hit_offsets
+            .iter()
+            .map(|offset| offset / clock_rate)
+            .collect::<Vec<_>>();
+
+        let mean_error = mean(&errors);
+        let variance = errors
+            .iter()
+            .map(|error| (error - mean_error).powi(2))
+            .sum::<f64>()
+            / errors.len() as f64;
+
+        let early = errors
+            .iter()
+            .copied()
+            .filter(|error| *error < 0.0)
+            .collect::<Vec<_>>();
+        let late = errors
+            .iter()
+            .copied()
+            .filter(|error| *error >= 0.0)
+            .collect::<Vec<_>>();
+
+        ReplayHitErrors {
+            mean_error,
+            unstable_rate: variance.sqrt() * 10.0,
+            early_count: early.len() as u32,
+            late_count: late.len() as u32,
+            early_mean: mean(&early),
+            late_mean: mean(&late),
+            histogram: histogram(&errors),
+            hit_offsets,
+        }
+    }
+}
+
+impl Replay
+{
+    /// Hit error statistics from the simulated judgements, see [`Replay::simulate_judgements`].
+    pub fn hit_errors(&self, beatmap: &ParserBeatmap) -> ParserResult<ReplayHitErrors>
+    {
+        Ok(self.simulate_judgements(beatmap)?.hit_errors(self.mods))
+    }
+}
+
+pub(crate) fn mean(values: &[f64]) -> f64
+{
+    if values.is_empty()
+    {
+        0.0
+    }
+    else
+    {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn histogram(errors: &[f64]) -> Vec<HitErrorBin>
+{
+    let bin_index = |error: f64| (error / HISTOGRAM_BIN_SIZE).floor() as i64;
+    let first = errors
+        .iter()
+        .map(|error| bin_index(*error))
+        .min()
+        .unwrap_or(0);
+    let last = errors
+        .iter()
+        .map(|error| bin_index(*error))
+        .max()
+        .unwrap_or(0);
+
+    let mut bins = (first..=last)
+        .map(|index| HitErrorBin {
+            offset: index as f64 * HISTOGRAM_BIN_SIZE,
+            count:  0,
+        })
+        .collect::<Vec<_>>();
+    for error in errors
+    {
+        bins[(bin_index(*error) - first) as usize].count += 1;
+    }
+    bins
+}
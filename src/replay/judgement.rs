@@ -0,0 +1,526 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{Buttons, Judgements, Mode, Mods, ParserError, ParserResult, Replay, ReplayFrame};
+use crate::beatmap::{
+    mods::scale_difficulty,
+    objects::{HitObjectKind, HitWindows, Pos2},
+    slider::{SliderData, SliderEventKind},
+    ParserBeatmap,
+};
+
+/// Clicks earlier than this before an object are ignored instead of counting as a miss.
+const MISS_WINDOW: f64 = 400.0;
+const SPINNER_CENTRE: Pos2 = Pos2 { x: 256.0, y: 192.0 };
+const SPINNER_STABLE_MATCHING_FUDGE: f64 = 0.6;
+const SPINNER_MAX_RPM: f64 = 477.0;
+const FOLLOW_CIRCLE_SCALE: f32 = 2.4;
+const SEED_FRAME: i32 = -12345;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum HitResult
+{
+    Hit300,
+    Hit100,
+    Hit50,
+    #[default]
+    Miss,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SliderJudgement
+{
+    pub head_hit:      bool,
+    pub ticks_hit:     u32,
+    pub ticks_total:   u32,
+    pub repeats_hit:   u32,
+    pub repeats_total: u32,
+    pub end_hit:       bool,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ObjectJudgement
+{
+    /// Index of the object in [`ParserBeatmap::hit_objects`].
+    pub object_index:      usize,
+    pub time:              f64,
+    pub result:            HitResult,
+    /// Press time relative to the object in ms, negative when early.
+    pub hit_offset:        Option<f64>,
+    /// Index of the frame in [`super::ReplayFrameData::frames`] that hit the object.
+    pub frame_index:       Option<usize>,
+    pub slider:            Option<SliderJudgement>,
+    pub spinner_rotations: Option<f64>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplayJudgements
+{
+    pub objects:    Vec<ObjectJudgement>,
+    pub judgements: Judgements,
+    pub max_combo:  u32,
+}
+
+/// Mod adjusted values used to judge objects.
+struct JudgementDifficulty
+{
+    radius:           f32,
+    hit_window_300:   f64,
+    hit_window_100:   f64,
+    hit_window_50:    f64,
+    spins_per_second: f64,
+    max_spin_rate:    f64,
+}
+
+impl JudgementDifficulty
+{
+    fn new(beatmap: &ParserBeatmap, mods: Mods) -> Self
+    {
+        let cs = scale_difficulty(beatmap.cs, mods, 1.3);
+        let od = scale_difficulty(beatmap.od, mods, 1.4);
+
+        Self {
+            radius:           circle_radius(cs),
+            hit_window_300:   HitWindows::Hit300.with_od(od) as f64,
+            hit_window_100:   HitWindows::Hit100.with_od(od) as f64,
+            hit_window_50:    HitWindows::Hit50.with_od(od) as f64,
+            spins_per_second: SPINNER_STABLE_MATCHING_FUDGE
+                * difficulty_range(od as f64, 3.0, 5.0, 7.5),
+            max_spin_rate:    SPINNER_MAX_RPM / 60.0 * 2.0 * PI / 1000.0 * mods.clock_rate(),
+        }
+    }
+
+    fn spins_required(&self, duration: f64) -> f64
+    {
+        (duration / 1000.0 * self.spins_per_second).floor()
+    }
+
+    fn result_for_offset(&self, offset: f64) -> HitResult
+    {
+        match offset.abs()
+        {
+            x if x <= self.hit_window_300 => HitResult::Hit300,
+            x if x <= self.hit_window_100 => HitResult::Hit100,
+            x if x <= self.hit_window_50 => HitResult::Hit50,
+            _ => HitResult::Miss,
+        }
+    }
+}
+
+/// Full spins needed to clear a spinner of the given duration in ms.
+pub(crate) fn spins_required(beatmap: &ParserBeatmap, mods: Mods, duration: f64) -> f64
+{
+    JudgementDifficulty::new(beatmap, mods).spins_required(duration)
+}
+
+/// Radius of hit circles in osu!pixels for a mod adjusted circle size.
+pub(crate) fn circle_radius(cs: f32) -> f32
+{
+    64.0 * (1.0 - 0.7 * (cs - 5.0) / 5.0) / 2.0 * 1.00041
+}
+
+pub(crate) fn difficulty_range(difficulty: f64, min: f64, mid: f64, max: f64) -> f64
+{
+    if difficulty > 5.0
+    {
+        mid + (max - mid) * (difficulty - 5.0) / 5.0
+    }
+    else if difficulty < 5.0
+    {
+        mid - (mid - min) * (5.0 - difficulty) / 5.0
+    }
+    else
+    {
+        mid
+    }
+}
+
+enum SimulationEvent
+{
+    HeadExpiry,
+    Slider(usize),
+    SpinnerEnd,
+}
+
+struct ObjectState
+{
+    pos:           Pos2,
+    slider:        Option<SliderData>,
+    spinner_end:   Option<f64>,
+    head_judged:   bool,
+    slider_ended:  bool,
+    spinner_angle: Option<f64>,
+    judgement:     ObjectJudgement,
+}
+
+struct Simulation
+{
+    difficulty: JudgementDifficulty,
+    objects:    Vec<ObjectState>,
+    next_head:  usize,
+    combo:      u32,
+    max_combo:  u32,
+}
+
+impl Simulation
+{
+    fn add_combo(&mut self, hit: bool)
+    {
+        if hit
+        {
+            self.combo += 1;
+            self.max_combo = self.max_combo.max(self.combo);
+        }
+        else
+        {
+            self.combo = 0;
+        }
+    }
+
+    fn judge_head(
+        &mut self, index: usize, result: HitResult, offset: Option<f64>, frame_index: Option<usize>,
+    )
+    {
+        let object = &mut self.objects[index];
+        object.head_judged = true;
+        object.judgement.frame_index = frame_index;
+        if result != HitResult::Miss
+        {
+            object.judgement.hit_offset = offset;
+        }
+        match object.judgement.slider.as_mut()
+        {
+            Some(slider) => slider.head_hit = result != HitResult::Miss,
+            None => object.judgement.result = result,
+        }
+        self.add_combo(result != HitResult::Miss);
+        self.finalize_slider(index);
+    }
+
+    fn finalize_slider(&mut self, index: usize)
+    {
+        let object = &mut self.objects[index];
+        if !object.head_judged || !object.slider_ended
+        {
+            return;
+        }
+        if let Some(slider) = object.judgement.slider
+        {
+            let total = 2 + slider.ticks_total + slider.repeats_total;
+            let hit = slider.head_hit as u32
+                + slider.ticks_hit
+                + slider.repeats_hit
+                + slider.end_hit as u32;
+            object.judgement.result = if hit == total
+            {
+                HitResult::Hit300
+            }
+            else if hit * 2 >= total
+            {
+                HitResult::Hit100
+            }
+            else if hit > 0
+            {
+                HitResult::Hit50
+            }
+            else
+            {
+                HitResult::Miss
+            };
+        }
+    }
+
+    fn press(&mut self, frame: &ReplayFrame, frame_index: usize)
+    {
+        while self.next_head < self.objects.len()
+            && (self.objects[self.next_head].head_judged
+                || self.objects[self.next_head].spinner_end.is_some())
+        {
+            self.next_head += 1;
+        }
+        // Note lock: only the earliest unjudged object can be hit
+        let index = self.next_head;
+        let object = match self.objects.get(index)
+        {
+            Some(object) => object,
+            None => return,
+        };
+
+        let offset = frame.timestamp_ms as f64 - object.judgement.time;
+        if offset < -MISS_WINDOW || frame.cursor_pos.distance(object.pos) > self.difficulty.radius
+        {
+            return;
+        }
+        let result = self.difficulty.result_for_offset(offset);
+        self.judge_head(index, result, Some(offset), Some(frame_index));
+    }
+
+    fn spin(&mut self, previous: &ReplayFrame, frame: &ReplayFrame)
+    {
+        let holding = previous.buttons.intersects(Buttons::M1 | Buttons::M2);
+        let time = frame.timestamp_ms as f64;
+        let elapsed = (frame.timestamp_ms - previous.timestamp_ms).max(0) as f64;
+        let angle = ((frame.cursor_pos.y - SPINNER_CENTRE.y) as f64)
+            .atan2((frame.cursor_pos.x - SPINNER_CENTRE.x) as f64);
+        let max_rotation = self.difficulty.max_spin_rate * elapsed;
+
+        for object in self.objects.iter_mut()
+        {
+            match object.spinner_end
+            {
+                Some(end_time) if time >= object.judgement.time && time <= end_time =>
+                {}
+                _ => continue,
+            }
+            if let (Some(last_angle), true) = (object.spinner_angle, holding)
+            {
+                let mut delta = angle - last_angle;
+                if delta > PI
+                {
+                    delta -= 2.0 * PI;
+                }
+                else if delta < -PI
+                {
+                    delta += 2.0 * PI;
+                }
+                let rotations = object.judgement.spinner_rotations.get_or_insert(0.0);
+                *rotations += delta.abs().min(max_rotation) / (2.0 * PI);
+            }
+            object.spinner_angle = Some(angle);
+        }
+    }
+
+    fn resolve(&mut self, index: usize, event: &SimulationEvent, state: Option<&ReplayFrame>)
+    {
+        let tracking = |pos: Pos2, radius: f32| {
+            state.is_some_and(|frame| {
+                frame.buttons.intersects(Buttons::M1 | Buttons::M2)
+                    && frame.cursor_pos.distance(pos) <= radius
+            })
+        };
+        let follow_radius = self.difficulty.radius * FOLLOW_CIRCLE_SCALE;
+
+        match event
+        {
+            SimulationEvent::HeadExpiry =>
+            {
+                if !self.objects[index].head_judged
+                {
+                    self.judge_head(index, HitResult::Miss, None, None);
+                }
+            }
+            SimulationEvent::Slider(event_index) =>
+            {
+                let object = &mut self.objects[index];
+                let slider_event = object.slider.as_ref().unwrap().events[*event_index];
+                let hit = tracking(slider_event.pos, follow_radius);
+                let judgement = object.judgement.slider.as_mut().unwrap();
+                match slider_event.kind
+                {
+                    SliderEventKind::Tick => judgement.ticks_hit += hit as u32,
+                    SliderEventKind::Repeat => judgement.repeats_hit += hit as u32,
+                    SliderEventKind::LegacyLastTick =>
+                    {
+                        judgement.end_hit = hit;
+                        object.slider_ended = true;
+                    }
+                }
+                if slider_event.kind == SliderEventKind::LegacyLastTick
+                {
+                    // Missing a slider end does not break combo
+                    if hit
+                    {
+                        self.add_combo(true);
+                    }
+                    self.finalize_slider(index);
+                }
+                else
+                {
+                    self.add_combo(hit);
+                }
+            }
+            SimulationEvent::SpinnerEnd =>
+            {
+                let object = &mut self.objects[index];
+                let duration = object.spinner_end.unwrap() - object.judgement.time;
+                let spins_required = self.difficulty.spins_required(duration);
+                let rotations = *object.judgement.spinner_rotations.get_or_insert(0.0);
+                let progress = if spins_required > 0.0
+                {
+                    rotations / spins_required
+                }
+                else
+                {
+                    1.0
+                };
+
+                object.judgement.result = match progress
+                {
+                    x if x >= 1.0 => HitResult::Hit300,
+                    x if x > 0.9 => HitResult::Hit100,
+                    x if x > 0.75 => HitResult::Hit50,
+                    _ => HitResult::Miss,
+                };
+                let hit = object.judgement.result != HitResult::Miss;
+                self.add_combo(hit);
+            }
+        }
+    }
+}
+
+impl Replay
+{
+    /// Simulates osu!standard judgements of the replay frames against the beatmap hit objects.
+    ///
+    /// Objects are hit tested at their stacked positions after the HR and Mirror flips, see
+    /// [`ParserBeatmap::stacking`]. Requires [`Replay::replay_frame_data`], see [`Replay::parse_extra`].
+    pub fn simulate_judgements(&self, beatmap: &ParserBeatmap) -> ParserResult<ReplayJudgements>
+    {
+        if !matches!(self.mode, Mode::Osu)
+        {
+            return Err(ParserError::UnsupportedMode(self.mode as u8));
+        }
+        let frames = &self
+            .replay_frame_data
+            .as_ref()
+            .ok_or(ParserError::ReplayFramesMissing)?
+            .frames;
+        let hit_objects = beatmap
+            .hit_objects
+            .as_ref()
+            .ok_or(ParserError::HitobjectsMissing)?;
+
+        let stacked = beatmap.stacking(self.mods).objects;
+        let mut simulation = Simulation {
+            difficulty: JudgementDifficulty::new(beatmap, self.mods),
+            objects:    Vec::new(),
+            next_head:  0,
+            combo:      0,
+            max_combo:  0,
+        };
+
+        let mut events: Vec<(f64, usize, SimulationEvent)> = Vec::new();
+        for ((object_index, object), stacked) in hit_objects.iter().enumerate().zip(&stacked)
+        {
+            let index = simulation.objects.len();
+            let mut state = ObjectState {
+                pos:           stacked.stacked_position,
+                slider:        None,
+                spinner_end:   None,
+                head_judged:   false,
+                slider_ended:  false,
+                spinner_angle: None,
+                judgement:     ObjectJudgement {
+                    object_index,
+                    time: object.start_time,
+                    ..Default::default()
+                },
+            };
+
+            match &object.kind
+            {
+                HitObjectKind::Circle =>
+                {}
+                HitObjectKind::Slider { .. } =>
+                {
+                    let mut slider = beatmap.slider_data(object).unwrap();
+                    let mut judgement = SliderJudgement::default();
+                    // The whole slider is moved by the stack offset of its head
+                    let stack_offset = stacked.stacked_position - stacked.position;
+                    for (event_index, event) in slider.events.iter_mut().enumerate()
+                    {
+                        event.pos = beatmap.flip_position(event.pos, self.mods) + stack_offset;
+                        match event.kind
+                        {
+                            SliderEventKind::Tick => judgement.ticks_total += 1,
+                            SliderEventKind::Repeat => judgement.repeats_total += 1,
+                            SliderEventKind::LegacyLastTick =>
+                            {}
+                        }
+                        events.push((event.time, index, SimulationEvent::Slider(event_index)));
+                    }
+                    state.slider = Some(slider);
+                    state.judgement.slider = Some(judgement);
+                }
+                HitObjectKind::Spinner { end_time } =>
+                {
+                    state.head_judged = true;
+                    state.spinner_end = Some(*end_time);
+                    events.push((*end_time, index, SimulationEvent::SpinnerEnd));
+                }
+                HitObjectKind::Hold { .. } => continue,
+            }
+            if state.spinner_end.is_none()
+            {
+                events.push((
+                    object.start_time + simulation.difficulty.hit_window_50,
+                    index,
+                    SimulationEvent::HeadExpiry,
+                ));
+            }
+            simulation.objects.push(state);
+        }
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut events = events.into_iter().peekable();
+        let mut previous: Option<&ReplayFrame> = None;
+        for (frame_index, frame) in frames.iter().enumerate()
+        {
+            if frame.time_since_ms == SEED_FRAME
+            {
+                continue;
+            }
+            let time = frame.timestamp_ms as f64;
+            while let Some((_, index, event)) =
+                events.next_if(|(event_time, ..)| *event_time < time)
+            {
+                simulation.resolve(index, &event, previous);
+            }
+
+            let previous_buttons = previous.map_or(Buttons::empty(), |previous| previous.buttons);
+            if let Some(previous) = previous
+            {
+                simulation.spin(previous, frame);
+            }
+            let presses = (frame.buttons & !previous_buttons & (Buttons::M1 | Buttons::M2))
+                .bits()
+                .count_ones();
+            for _ in 0..presses
+            {
+                simulation.press(frame, frame_index);
+            }
+            previous = Some(frame);
+        }
+        for (_, index, event) in events
+        {
+            simulation.resolve(index, &event, previous);
+        }
+
+        let objects = simulation
+            .objects
+            .into_iter()
+            .map(|object| object.judgement)
+            .collect::<Vec<_>>();
+        let count =
+            |result: HitResult| Some(objects.iter().filter(|x| x.result == result).count() as u16);
+
+        Ok(ReplayJudgements {
+            judgements: Judgements {
+                count_300:  count(HitResult::Hit300),
+                count_100:  count(HitResult::Hit100),
+                count_50:   count(HitResult::Hit50),
+                count_geki: None,
+                count_katu: None,
+                miss:       count(HitResult::Miss),
+            },
+            objects,
+            max_combo: simulation.max_combo,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+use std::{collections::BTreeMap, io::Read};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::ParserResult;
+
+/// First replay version written by osu!lazer.
+pub const FIRST_LAZER_VERSION: u32 = 30000000;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LazerMod
+{
+    pub acronym:  String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[tsify(type = "Record<string, any>")]
+    pub settings: BTreeMap<String, serde_json::Value>,
+}
+
+/// Score information appended to replays exported by osu!lazer.
+///
+/// Judgement statistics are keyed by the lazer hit result name, e.g. `great`, `ok`, `meh`,
+/// `miss`, `large_tick_hit` or `slider_tail_hit`. Fields without their own member, like
+/// `pauses` or `total_score`, are kept in `extra`.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct LazerScoreInfo
+{
+    #[serde(default)]
+    pub online_id:                i64,
+    #[serde(default)]
+    pub mods:                     Vec<LazerMod>,
+    #[serde(default)]
+    pub statistics:               BTreeMap<String, u32>,
+    #[serde(default)]
+    pub maximum_statistics:       BTreeMap<String, u32>,
+    #[serde(default)]
+    pub client_version:           Option<String>,
+    #[serde(default)]
+    pub rank:                     Option<String>,
+    #[serde(default)]
+    pub user_id:                  Option<i64>,
+    #[serde(default)]
+    pub total_score_without_mods: Option<i64>,
+    /// Every other field of the block, kept so writing the replay does not lose them.
+    #[serde(flatten)]
+    #[tsify(type = "Record<string, any>")]
+    pub extra:                    serde_json::Map<String, serde_json::Value>,
+}
+
+impl LazerScoreInfo
+{
+    /// Reads the length prefixed, LZMA compressed JSON score info block.
+    ///
+    /// Returns [`None`] if the replay ends before the block or the block is empty.
+    pub fn parse<R: Read>(replay: &mut R) -> ParserResult<Option<Self>>
+    {
+        let length = match replay.read_i32::<LittleEndian>()
+        {
+            Ok(length) if length > 0 => length,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut compressed = vec![0; length as usize];
+        replay.read_exact(&mut compressed)?;
+
+        let mut decompressed = Vec::new();
+        lzma_rs::lzma_decompress(&mut compressed.as_slice(), &mut decompressed)?;
+        Ok(Some(serde_json::from_slice(&decompressed)?))
+    }
+
+    /// Serializes the score info back into its compressed JSON form.
+    pub fn compress(&self) -> ParserResult<Vec<u8>>
+    {
+        let json = serde_json::to_vec(self)?;
+        let mut compressed = Vec::new();
+        lzma_rs::lzma_compress(&mut json.as_slice(), &mut compressed)?;
+        Ok(compressed)
+    }
+}
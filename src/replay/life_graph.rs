@@ -0,0 +1,513 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    judgement::{difficulty_range, spins_required, HitResult, ReplayJudgements},
+    LifegraphData, Mode, Mods, ParserError, ParserResult, Replay,
+};
+use crate::beatmap::{
+    mods::scale_difficulty,
+    objects::{HitObjectKind, HitWindows},
+    slider::SliderEventKind,
+    ParserBeatmap,
+};
+
+/// Health gains of osu! stable, out of a full health of 1.
+const HP_HIT_300: f64 = 0.03;
+const HP_HIT_100: f64 = 0.011;
+const HP_HIT_50: f64 = 0.002;
+const HP_SLIDER_REPEAT: f64 = 0.02;
+const HP_SLIDER_TICK: f64 = 0.015;
+const HP_SPINNER_SPIN: f64 = 0.0085;
+const HP_SPINNER_BONUS: f64 = 0.01;
+/// Health lost on a missed object at HP 0, 5 and 10.
+const HP_MISS: [f64; 3] = [-0.03, -0.125, -0.2];
+/// Health lost on a missed slider head, tick, repeat or end at HP 0, 5 and 10.
+const HP_TICK_MISS: [f64; 3] = [-0.02, -0.075, -0.14];
+/// Drain per ms the drain rate search starts from.
+const INITIAL_DRAIN: f64 = 0.00025;
+/// First beatmap version that stops draining at the end of the object before a break.
+const BREAK_DRAIN_FORMAT_VERSION: u32 = 8;
+/// Interval in ms health is sampled at between judgements.
+const SAMPLE_INTERVAL: f64 = 50.0;
+
+/// Health of a play over time, between 0 and 1, as osu! stable drains it.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplayLifeGraph
+{
+    /// Health after every judgement and every 50ms in between.
+    pub points:     Vec<LifegraphData>,
+    /// Health drained per ms outside of breaks.
+    pub drain_rate: f64,
+    /// Time health first reached 0, also set for plays with NF.
+    pub fail_time:  Option<f64>,
+    /// Whether the play reached its end, always true with NF.
+    pub passed:     bool,
+}
+
+/// Change of health at a time.
+#[derive(Clone, Copy)]
+struct HealthEvent
+{
+    time:   f64,
+    health: f64,
+}
+
+/// Object as seen by the drain rate search of osu! stable.
+struct DrainObject
+{
+    start_time: f64,
+    end_time:   f64,
+    new_combo:  bool,
+    /// Health of the slider heads, repeats, ends and ticks or spinner spins before the multiplier.
+    nested:     f64,
+}
+
+/// Health with and without the cap at 1.
+struct DrainHealth
+{
+    current:  f64,
+    uncapped: f64,
+}
+
+impl DrainHealth
+{
+    fn reduce(&mut self, amount: f64)
+    {
+        self.current = (self.current - amount).max(0.0);
+        self.uncapped = (self.uncapped - amount).max(0.0);
+    }
+
+    fn increase(&mut self, amount: f64)
+    {
+        self.current = (self.current + amount).clamp(0.0, 1.0);
+        self.uncapped += amount;
+    }
+}
+
+impl ReplayJudgements
+{
+    /// Reconstructs the osu!standard health of the play the way osu! stable drains it, as
+    /// documented by the `OsuLegacyHealthProcessor` of osu!lazer.
+    ///
+    /// The drain rate and the multiplier of health gains are searched from the HP of the beatmap
+    /// like stable does, and judgements use the health gains and losses of stable. Slider ticks and
+    /// repeats only have hit counts, so missed ones are assumed to come first, and spinner spins
+    /// count at the end of the spinner.
+    pub fn life_graph(&self, beatmap: &ParserBeatmap, mods: Mods) -> ParserResult<ReplayLifeGraph>
+    {
+        if beatmap.mode != Mode::Osu
+        {
+            return Err(ParserError::UnsupportedMode(beatmap.mode as u8));
+        }
+        let hit_objects = beatmap
+            .hit_objects
+            .as_ref()
+            .ok_or(ParserError::HitobjectsMissing)?;
+        let hp = scale_difficulty(beatmap.hp, mods, 1.4) as f64;
+        let hit_window_50 =
+            HitWindows::Hit50.with_od(scale_difficulty(beatmap.od, mods, 1.4)) as f64;
+        let miss = difficulty_range(hp, HP_MISS[0], HP_MISS[1], HP_MISS[2]);
+        let tick_miss = difficulty_range(hp, HP_TICK_MISS[0], HP_TICK_MISS[1], HP_TICK_MISS[2]);
+
+        let drain_objects = hit_objects
+            .iter()
+            .map(|hit_object| match &hit_object.kind
+            {
+                HitObjectKind::Slider { .. } =>
+                {
+                    let data = beatmap.slider_data(hit_object);
+                    let nested = data.as_ref().map_or(HP_SLIDER_REPEAT, |data| {
+                        data.events
+                            .iter()
+                            .map(|event| match event.kind
+                            {
+                                SliderEventKind::Tick => HP_SLIDER_TICK,
+                                _ => HP_SLIDER_REPEAT,
+                            })
+                            .sum::<f64>()
+                            + HP_SLIDER_REPEAT
+                    });
+                    DrainObject {
+                        start_time: hit_object.start_time,
+                        end_time: data.map_or(hit_object.start_time, |data| data.end_time),
+                        new_combo: hit_object.new_combo,
+                        nested,
+                    }
+                }
+                HitObjectKind::Spinner { end_time } => DrainObject {
+                    start_time: hit_object.start_time,
+                    end_time:   *end_time,
+                    new_combo:  hit_object.new_combo,
+                    nested:     spins_required(beatmap, mods, end_time - hit_object.start_time)
+                        * HP_SPINNER_SPIN,
+                },
+                _ => DrainObject {
+                    start_time: hit_object.start_time,
+                    end_time:   hit_object.start_time,
+                    new_combo:  hit_object.new_combo,
+                    nested:     0.0,
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let legacy_breaks = beatmap.format_version < BREAK_DRAIN_FORMAT_VERSION;
+        let breaks = beatmap
+            .breaks
+            .iter()
+            .flatten()
+            .map(|break_period| (break_period.start_time as f64, break_period.end_time as f64))
+            .collect::<Vec<_>>();
+        let drain_start = drain_objects
+            .first()
+            .map_or(0.0, |object| object.start_time);
+        let drain_end = drain_objects
+            .iter()
+            .map(|object| object.end_time)
+            .fold(drain_start, f64::max);
+        let (drain_rate, multiplier) =
+            drain_rate(&drain_objects, &breaks, legacy_breaks, drain_start, hp);
+
+        // Health does not drain during breaks, and from version 8 on neither between the end of
+        // the object before a break and its start
+        let mut pauses = breaks
+            .iter()
+            .map(|&(start, end)| {
+                let start = if legacy_breaks
+                {
+                    start
+                }
+                else
+                {
+                    drain_objects
+                        .iter()
+                        .map(|object| object.end_time)
+                        .filter(|end_time| *end_time <= start)
+                        .fold(f64::NEG_INFINITY, f64::max)
+                        .max(drain_start)
+                };
+                (start, end)
+            })
+            .collect::<Vec<_>>();
+        pauses.push((drain_end, f64::INFINITY));
+
+        let hit = |result: HitResult| match result
+        {
+            HitResult::Hit300 => HP_HIT_300 * multiplier,
+            HitResult::Hit100 => HP_HIT_100 * multiplier,
+            HitResult::Hit50 => HP_HIT_50 * multiplier,
+            HitResult::Miss => miss,
+        };
+        let tick = |hit: bool, increase: f64| {
+            if hit
+            {
+                increase * multiplier
+            }
+            else
+            {
+                tick_miss
+            }
+        };
+
+        let mut events = Vec::new();
+        for object in &self.objects
+        {
+            let hit_object = match hit_objects.get(object.object_index)
+            {
+                Some(hit_object) => hit_object,
+                None => continue,
+            };
+            match (&hit_object.kind, object.slider)
+            {
+                (HitObjectKind::Slider { .. }, Some(slider)) =>
+                {
+                    let data = match beatmap.slider_data(hit_object)
+                    {
+                        Some(data) => data,
+                        None => continue,
+                    };
+                    let head_time = match object.hit_offset
+                    {
+                        Some(offset) if slider.head_hit => object.time + offset,
+                        _ => object.time + hit_window_50,
+                    };
+                    events.push(HealthEvent {
+                        time:   head_time,
+                        health: tick(slider.head_hit, HP_SLIDER_REPEAT),
+                    });
+
+                    let mut missed_ticks = slider.ticks_total - slider.ticks_hit;
+                    let mut missed_repeats = slider.repeats_total - slider.repeats_hit;
+                    for event in &data.events
+                    {
+                        let health = match event.kind
+                        {
+                            SliderEventKind::Tick =>
+                            {
+                                tick(!take_miss(&mut missed_ticks), HP_SLIDER_TICK)
+                            }
+                            SliderEventKind::Repeat =>
+                            {
+                                tick(!take_miss(&mut missed_repeats), HP_SLIDER_REPEAT)
+                            }
+                            SliderEventKind::LegacyLastTick =>
+                            {
+                                tick(slider.end_hit, HP_SLIDER_REPEAT)
+                            }
+                        };
+                        events.push(HealthEvent {
+                            time: event.time,
+                            health,
+                        });
+                    }
+                    events.push(HealthEvent {
+                        time:   data.end_time,
+                        health: hit(object.result),
+                    });
+                }
+                (HitObjectKind::Spinner { end_time }, _) =>
+                {
+                    let required = spins_required(beatmap, mods, end_time - hit_object.start_time);
+                    let spins = object.spinner_rotations.unwrap_or_default().floor();
+                    let spin_health = spins.min(required) * HP_SPINNER_SPIN
+                        + (spins - required).max(0.0) * HP_SPINNER_BONUS;
+                    events.push(HealthEvent {
+                        time:   *end_time,
+                        health: spin_health * multiplier + hit(object.result),
+                    });
+                }
+                _ =>
+                {
+                    let time = match object.hit_offset
+                    {
+                        Some(offset) => object.time + offset,
+                        None => object.time + hit_window_50,
+                    };
+                    events.push(HealthEvent {
+                        time,
+                        health: hit(object.result),
+                    });
+                }
+            }
+        }
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let no_fail = mods.contains(Mods::NoFail);
+        let mut graph = ReplayLifeGraph {
+            points: vec![life_point(drain_start, 1.0)],
+            drain_rate,
+            passed: true,
+            ..Default::default()
+        };
+        let mut health = 1.0;
+        let mut last_time = drain_start;
+        for event in &events
+        {
+            // Sample the drain between judgements
+            let mut sample_time = last_time + SAMPLE_INTERVAL;
+            while sample_time < event.time
+            {
+                let sample = health - drain_duration(last_time, sample_time, &pauses) * drain_rate;
+                // The fail itself is added below
+                if sample <= 0.0 && !no_fail
+                {
+                    break;
+                }
+                graph.points.push(life_point(sample_time, sample.max(0.0)));
+                sample_time += SAMPLE_INTERVAL;
+            }
+
+            let drained = drain_duration(last_time, event.time, &pauses) * drain_rate;
+            if health - drained <= 0.0 && graph.fail_time.is_none()
+            {
+                let time = fail_time(last_time, health, drain_rate, &pauses);
+                graph.fail_time = Some(time);
+                // Gameplay ends on failing without NF
+                if !no_fail
+                {
+                    graph.points.push(life_point(time, 0.0));
+                    graph.passed = false;
+                    break;
+                }
+            }
+            health = ((health - drained).max(0.0) + event.health).clamp(0.0, 1.0);
+            graph.points.push(life_point(event.time, health));
+            last_time = event.time;
+
+            if health <= 0.0 && graph.fail_time.is_none()
+            {
+                graph.fail_time = Some(event.time);
+                if !no_fail
+                {
+                    graph.passed = false;
+                    break;
+                }
+            }
+        }
+        Ok(graph)
+    }
+}
+
+impl Replay
+{
+    /// Reconstructs the health of the replay from its simulated judgements, see
+    /// [`ReplayJudgements::life_graph`] and [`Replay::simulate_judgements`].
+    pub fn reconstruct_life_graph(&self, beatmap: &ParserBeatmap) -> ParserResult<ReplayLifeGraph>
+    {
+        self.simulate_judgements(beatmap)?
+            .life_graph(beatmap, self.mods)
+    }
+}
+
+fn life_point(time: f64, health: f64) -> LifegraphData
+{
+    LifegraphData {
+        time:       time.round() as i32,
+        life_value: health,
+    }
+}
+
+/// Counts down the remaining misses, returns whether this one is missed.
+fn take_miss(missed: &mut u32) -> bool
+{
+    let miss = *missed > 0;
+    *missed = missed.saturating_sub(1);
+    miss
+}
+
+/// Time between two times without pauses.
+fn drain_duration(start: f64, end: f64, pauses: &[(f64, f64)]) -> f64
+{
+    let paused = pauses
+        .iter()
+        .map(|(pause_start, pause_end)| (end.min(*pause_end) - start.max(*pause_start)).max(0.0))
+        .sum::<f64>();
+    (end - start - paused).max(0.0)
+}
+
+/// First time health drains down to 0 from `start`, skipping pauses.
+fn fail_time(start: f64, health: f64, drain_rate: f64, pauses: &[(f64, f64)]) -> f64
+{
+    let mut time = start + health / drain_rate;
+    // Pauses delay the fail by the part of them before it
+    for (pause_start, pause_end) in pauses
+    {
+        if *pause_end > start && *pause_start < time
+        {
+            time += pause_end - pause_start.max(start);
+        }
+    }
+    time
+}
+
+/// Searches the drain rate and the multiplier of health gains like osu! stable, lowering the
+/// drain and raising the gains until a perfect play stays above the health limits of the HP.
+fn drain_rate(
+    objects: &[DrainObject], breaks: &[(f64, f64)], legacy_breaks: bool, drain_start: f64, hp: f64,
+) -> (f64, f64)
+{
+    if objects.is_empty()
+    {
+        return (0.0, 1.0);
+    }
+    let lowest_hp_ever = difficulty_range(hp, 0.975, 0.8, 0.3);
+    let lowest_hp_combo_end = difficulty_range(hp, 0.99, 0.85, 0.4);
+    let lowest_hp_end = difficulty_range(hp, 0.99, 0.9, 0.4);
+    let hp_recovery_available = difficulty_range(hp, 0.04, 0.02, 0.0);
+
+    let mut test_drop = INITIAL_DRAIN;
+    let mut multiplier_normal = 1.0;
+    let mut multiplier_combo_end = 1.0;
+    loop
+    {
+        let mut health = DrainHealth {
+            current:  1.0,
+            uncapped: 1.0,
+        };
+        let mut last_time = drain_start;
+        let mut next_break = 0;
+        let mut combo_too_low = 0;
+        let mut fail = false;
+
+        for (i, object) in objects.iter().enumerate()
+        {
+            // Subtract the break since the last object from the drained time
+            let mut break_time = 0.0;
+            if let Some(&(break_start, break_end)) = breaks.get(next_break)
+            {
+                if break_start >= last_time && break_end <= object.start_time
+                {
+                    break_time = if legacy_breaks
+                    {
+                        break_end - break_start
+                    }
+                    else
+                    {
+                        break_end - last_time
+                    };
+                    next_break += 1;
+                }
+            }
+            health.reduce(test_drop * (object.start_time - last_time - break_time));
+            last_time = object.end_time;
+            if health.current < lowest_hp_ever
+            {
+                fail = true;
+                test_drop *= 0.96;
+                break;
+            }
+
+            let reduction = test_drop * (object.end_time - object.start_time);
+            let overkill = (reduction - health.current).max(0.0);
+            health.reduce(reduction);
+            health.increase(multiplier_normal * object.nested);
+            if overkill > 0.0 && health.current - overkill <= lowest_hp_ever
+            {
+                fail = true;
+                test_drop *= 0.96;
+                break;
+            }
+
+            if i + 1 == objects.len() || objects[i + 1].new_combo
+            {
+                health.increase(multiplier_combo_end * HP_HIT_300);
+                if health.current < lowest_hp_combo_end
+                {
+                    combo_too_low += 1;
+                    if combo_too_low > 2
+                    {
+                        multiplier_combo_end *= 1.07;
+                        multiplier_normal *= 1.03;
+                        fail = true;
+                        break;
+                    }
+                }
+            }
+            else
+            {
+                health.increase(multiplier_normal * HP_HIT_300);
+            }
+        }
+
+        if !fail && health.current < lowest_hp_end
+        {
+            fail = true;
+            test_drop *= 0.94;
+            multiplier_combo_end *= 1.01;
+            multiplier_normal *= 1.01;
+        }
+        let recovery = (health.uncapped - 1.0) / objects.len() as f64;
+        if !fail && recovery < hp_recovery_available
+        {
+            fail = true;
+            test_drop *= 0.96;
+            multiplier_combo_end *= 1.02;
+            multiplier_normal *= 1.01;
+        }
+        if !fail
+        {
+            return (test_drop, multiplier_normal);
+        }
+    }
+}
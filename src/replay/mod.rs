@@ -1,4 +1,17 @@
+pub mod analysis;
+pub mod grade;
+pub mod hit_errors;
+pub mod judgement;
+pub mod lazer;
+pub mod life_graph;
 pub mod parse;
+pub mod score;
+pub mod similarity;
+pub mod stats;
+pub mod timewarp;
+pub mod write;
+use std::convert::TryFrom;
+
 use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
@@ -6,7 +19,7 @@ use tsify::Tsify;
 
 use crate::beatmap::objects::Pos2;
 
-use self::parse::LEB128Error;
+use self::{lazer::LazerScoreInfo, parse::LEB128Error};
 
 pub type ParserResult<T, E = ParserError> = std::result::Result<T, E>;
 
@@ -28,6 +41,18 @@ pub enum ParserError {
     #[error("error parsing float: {0}")]
     ParseFloat(#[from] std::num::ParseFloatError),
 
+    #[error("error parsing json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("error reading zip archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("zip archive file {0} is larger than {1} bytes")]
+    ZipEntryTooLarge(String, u64),
+
+    #[error("zip archive is larger than {0} bytes")]
+    ZipTooLarge(u64),
+
     #[error("error parsing string")]
     ParseString(#[from] crate::replay::LEB128Error),
 
@@ -39,9 +64,15 @@ pub enum ParserError {
     #[error("unexpected mods: {0}")]
     UnexpectedMods(u32),
 
+    #[error("invalid rate: {0}")]
+    InvalidRate(f64),
+
     #[error("invalid mode: {0}")]
     InvalidMode(u8),
 
+    #[error("invalid grade: {0}")]
+    InvalidGrade(u8),
+
     #[error("invalid buttons: {0}")]
     InvalidButtons(u32),
     #[error("error parsing beatmap: {0}")]
@@ -50,11 +81,23 @@ pub enum ParserError {
     #[error("error parsing libosu beatmap: {0}")]
     LibosuBeatmapParseError(#[from] libosu::prelude::BeatmapParseError),
 
+    #[error("missing replay frame data")]
+    ReplayFramesMissing,
+
+    #[error("unsupported mode: {0}")]
+    UnsupportedMode(u8),
+
+    #[error("{0:?} beatmaps cannot be converted to {1:?}")]
+    UnsupportedConversion(Mode, Mode),
+
+    #[error("replays are of different beatmaps: {0} and {1}")]
+    ReplayBeatmapMismatch(String, String),
+
     #[error("Beatmap and Replay hash mismatch, replay -> {0} beatmap -> {1}")]
     BeatmapHashMismatch(String, String),
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub enum Mode {
     #[default]
@@ -63,6 +106,51 @@ pub enum Mode {
     Catch = 2,
     Mania = 3,
 }
+impl TryFrom<u8> for Mode {
+    type Error = ParserError;
+
+    fn try_from(value: u8) -> ParserResult<Self> {
+        match value {
+            0 => Ok(Mode::Osu),
+            1 => Ok(Mode::Taiko),
+            2 => Ok(Mode::Catch),
+            3 => Ok(Mode::Mania),
+            x => Err(ParserError::InvalidMode(x)),
+        }
+    }
+}
+/// Letter grade of a score, numbered like osu! stable stores it.
+#[derive(Serialize, Deserialize, Debug, Tsify, Clone, Copy, PartialEq, Eq)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum Grade {
+    SSH = 0,
+    SH = 1,
+    SS = 2,
+    S = 3,
+    A = 4,
+    B = 5,
+    C = 6,
+    D = 7,
+    F = 8,
+}
+impl TryFrom<u8> for Grade {
+    type Error = ParserError;
+
+    fn try_from(value: u8) -> ParserResult<Self> {
+        match value {
+            0 => Ok(Grade::SSH),
+            1 => Ok(Grade::SH),
+            2 => Ok(Grade::SS),
+            3 => Ok(Grade::S),
+            4 => Ok(Grade::A),
+            5 => Ok(Grade::B),
+            6 => Ok(Grade::C),
+            7 => Ok(Grade::D),
+            8 => Ok(Grade::F),
+            x => Err(ParserError::InvalidGrade(x)),
+        }
+    }
+}
 #[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 
@@ -107,7 +195,7 @@ mod integer_representation {
 
 bitflags! {
 
-    #[derive(Default, Serialize, Deserialize, Debug)]
+    #[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
     #[serde(transparent)]
 
     pub struct Mods: u32 {
@@ -145,8 +233,20 @@ bitflags! {
         const Mirror = 1073741824;
     }
 }
+impl Mods {
+    /// Playback rate of the beatmap under DT/NC or HT.
+    pub fn clock_rate(&self) -> f64 {
+        if self.intersects(Mods::DoubleTime | Mods::Nightcore) {
+            1.5
+        } else if self.contains(Mods::HalfTime) {
+            0.75
+        } else {
+            1.0
+        }
+    }
+}
 bitflags! {
-    #[derive(Default, Serialize, Deserialize, Debug)]
+    #[derive(Default, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
     #[serde(transparent)]
     pub struct Buttons: u32 {
         const M1 = 1;
@@ -193,4 +293,6 @@ pub struct Replay {
     pub replay_data: Option<Vec<u8>>,
     pub score_id: Option<String>,
     pub replay_frame_data: Option<ReplayFrameData>,
+    pub target_practice_accuracy: Option<f64>,
+    pub lazer_score_info: Option<LazerScoreInfo>,
 }
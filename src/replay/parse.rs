@@ -1,6 +1,9 @@
-use std::io::Read;
+use std::{convert::TryFrom, io::Read};
 
-use super::{Judgements, LifegraphData, Mods, ParserResult, Replay, ReplayFrameData};
+use super::{
+    lazer::{LazerScoreInfo, FIRST_LAZER_VERSION},
+    Judgements, LifegraphData, Mods, ParserResult, Replay, ReplayFrameData,
+};
 use crate::{
     beatmap::{objects::Pos2, ParserBeatmap},
     replay::{Buttons, Mode, ParserError, ReplayFrame},
@@ -87,14 +90,7 @@ impl Replay
 {
     pub fn parse<R: Read>(replay: &mut R, extra: bool) -> ParserResult<Replay>
     {
-        let mode = match replay.read_u8()?
-        {
-            0 => Mode::Osu,
-            1 => Mode::Taiko,
-            2 => Mode::Catch,
-            3 => Mode::Mania,
-            x => return Err(ParserError::InvalidMode(x)),
-        };
+        let mode = Mode::try_from(replay.read_u8()?)?;
 
         let version = replay.read_u32::<LittleEndian>()?;
         let beatmap_hash = replay.read_uleb128_string()?;
@@ -139,19 +135,49 @@ impl Replay
             .collect::<ParserResult<Vec<_>>>()?;
 
         let timestamp = replay.read_u64::<LittleEndian>()?;
-        let replay_data_length = replay.read_u32::<LittleEndian>()?;
+        // scores.db entries have no replay data and store a length of -1
+        let replay_data_length = match replay.read_i32::<LittleEndian>()?
+        {
+            -1 => 0,
+            length => length as u32,
+        };
         let mut replay_data = None;
         if extra
         {
             replay_data = Some(vec![0; replay_data_length as usize]);
             replay.read_exact(&mut replay_data.as_mut().unwrap())?;
         }
+        else
+        {
+            std::io::copy(
+                &mut replay.by_ref().take(replay_data_length as u64),
+                &mut std::io::sink(),
+            )?;
+        }
 
         let score_id = match replay.read_u64::<LittleEndian>()?
         {
             0 => None,
             v => Some(v.to_string()),
         };
+        let mods = Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?;
+
+        let target_practice_accuracy = if mods.contains(Mods::TargetPractice)
+        {
+            Some(replay.read_f64::<LittleEndian>()?)
+        }
+        else
+        {
+            None
+        };
+        let lazer_score_info = if version >= FIRST_LAZER_VERSION
+        {
+            LazerScoreInfo::parse(replay)?
+        }
+        else
+        {
+            None
+        };
 
         Ok(Replay {
             mode,
@@ -170,11 +196,13 @@ impl Replay
             score,
             max_combo,
             perfect,
-            mods: Mods::from_bits(mods).ok_or(ParserError::UnexpectedMods(mods))?,
+            mods,
             life_graph,
             timestamp: timestamp.to_string(),
             replay_data: replay_data,
             score_id,
+            target_practice_accuracy,
+            lazer_score_info,
             ..Default::default()
         })
     }
@@ -182,6 +210,14 @@ impl Replay
     pub fn parse_extra<R: Read + Clone + std::convert::AsRef<[u8]>>(
         replay: &mut R, beatmap: &mut R,
     ) -> ParserResult<Replay>
+    {
+        Ok(Replay::parse_with_beatmap(replay, beatmap)?.0)
+    }
+
+    /// Same as [`Replay::parse_extra`], but also returns the parsed beatmap.
+    pub fn parse_with_beatmap<R: Read + Clone + std::convert::AsRef<[u8]>>(
+        replay: &mut R, beatmap: &mut R,
+    ) -> ParserResult<(Replay, ParserBeatmap)>
     {
         let mut replay = Replay::parse(replay, true)?;
         let beatmap = ParserBeatmap::parse(beatmap)?;
@@ -194,9 +230,20 @@ impl Replay
             ));
         }
 
+        replay.decode_frames()?;
+        Ok((replay, beatmap))
+    }
+
+    /// Decompresses [`Replay::replay_data`] into [`Replay::replay_frame_data`].
+    pub fn decode_frames(&mut self) -> ParserResult<()>
+    {
         let mut decoded_data = Vec::new();
         lzma_rs::lzma_decompress(
-            &mut replay.replay_data.as_mut().unwrap().as_slice(),
+            &mut self
+                .replay_data
+                .as_ref()
+                .ok_or(ParserError::ReplayFramesMissing)?
+                .as_slice(),
             &mut decoded_data,
         )?;
         let replay_data = String::from_utf8(decoded_data)?;
@@ -238,7 +285,7 @@ impl Replay
             .filter(|x| x.time_since_ms == -12345)
             .and_then(|x| Some(x.buttons.bits()));
 
-        replay.replay_frame_data = Some(ReplayFrameData { frames, seed });
-        Ok(replay)
+        self.replay_frame_data = Some(ReplayFrameData { frames, seed });
+        Ok(())
     }
 }
@@ -0,0 +1,374 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    judgement::{spins_required, HitResult, ReplayJudgements},
+    Judgements, Mode, Mods, ParserError, ParserResult, Replay,
+};
+use crate::beatmap::{objects::HitObjectKind, slider::SliderEventKind, ParserBeatmap};
+
+const SLIDER_TICK_SCORE: f64 = 10.0;
+/// Score of slider heads, repeats and ends.
+const SLIDER_EDGE_SCORE: f64 = 30.0;
+/// Score of every full spin until the spinner is cleared.
+const SPINNER_SPIN_SCORE: f64 = 100.0;
+/// Score of every full spin after the spinner is cleared.
+const SPINNER_BONUS_SCORE: f64 = 1000.0;
+const SCORE_V2_COMBO_PORTION: f64 = 700_000.0;
+/// Every this much combo adds the hit value once more to its ScoreV2 weight.
+const SCORE_V2_COMBO_DIVISOR: f64 = 10.0;
+const SCORE_V2_ACCURACY_PORTION: f64 = 300_000.0;
+
+/// ScoreV1 and ScoreV2 of an osu!standard play.
+///
+/// The ScoreV2 portions are before the mod multiplier, the totals include it.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ScoreCalculation
+{
+    pub score_v1:         u32,
+    pub score_v2:         u32,
+    /// Combo weighted hit values relative to a full combo with only 300s, out of 700000.
+    pub combo_portion:    f64,
+    pub accuracy_portion: f64,
+    pub spinner_bonus:    f64,
+    /// Whether the score was estimated from judgement counts instead of every object.
+    pub estimated:        bool,
+}
+
+/// Scoring values of a beatmap which do not depend on the play.
+struct ScoreContext
+{
+    objects:             usize,
+    max_combo:           f64,
+    /// Combo weighted hit values of a full combo with only 300s, see [`combo_score`].
+    max_combo_score:     f64,
+    /// Score of every slider tick, repeat and end.
+    slider_score:        f64,
+    /// Combo multiplier of ScoreV1, the difficulty multiplier times the mod multiplier.
+    score_multiplier:    f64,
+    score_v2_multiplier: f64,
+}
+
+impl ScoreContext
+{
+    fn new(beatmap: &ParserBeatmap, mods: Mods) -> ParserResult<Self>
+    {
+        if beatmap.mode != Mode::Osu
+        {
+            return Err(ParserError::UnsupportedMode(beatmap.mode as u8));
+        }
+        let hit_objects = beatmap
+            .hit_objects
+            .as_ref()
+            .ok_or(ParserError::HitobjectsMissing)?;
+
+        let (mut slider_score, mut combo, mut max_combo_score) = (0.0, 0.0, 0.0);
+        for object in hit_objects
+        {
+            if let Some(data) = beatmap.slider_data(object)
+            {
+                for event in data.events
+                {
+                    slider_score += match event.kind
+                    {
+                        SliderEventKind::Tick => SLIDER_TICK_SCORE,
+                        SliderEventKind::Repeat | SliderEventKind::LegacyLastTick =>
+                        {
+                            SLIDER_EDGE_SCORE
+                        }
+                    };
+                    combo += 1.0;
+                }
+                slider_score += SLIDER_EDGE_SCORE;
+            }
+            combo += 1.0;
+            max_combo_score += combo_score(300.0, combo);
+        }
+
+        Ok(Self {
+            objects: hit_objects.len(),
+            max_combo: combo,
+            max_combo_score,
+            slider_score,
+            score_multiplier: difficulty_multiplier(beatmap) * score_v1_mod_multiplier(mods),
+            score_v2_multiplier: score_v2_mod_multiplier(mods),
+        })
+    }
+
+    /// Score of a hit including its ScoreV1 combo bonus, from the combo before the hit.
+    fn hit_score(&self, value: f64, combo: f64) -> f64
+    {
+        value + value * ((combo - 1.0).max(0.0) * self.score_multiplier / 25.0)
+    }
+
+    /// Combo and accuracy portion of ScoreV2, from the combo weighted hit values relative to the
+    /// ones of a full combo with only 300s.
+    fn score_v2(&self, judgements: &Judgements, combo_ratio: f64) -> (f64, f64)
+    {
+        let (hits, judged) = judged_values(judgements);
+        let accuracy = if judged > 0.0
+        {
+            hits / (judged * 300.0)
+        }
+        else
+        {
+            0.0
+        };
+        let combo_portion = SCORE_V2_COMBO_PORTION * combo_ratio.clamp(0.0, 1.0);
+        let accuracy_portion = if self.objects > 0
+        {
+            SCORE_V2_ACCURACY_PORTION * accuracy.powi(10) * judged / self.objects as f64
+        }
+        else
+        {
+            0.0
+        };
+        (combo_portion, accuracy_portion)
+    }
+
+    fn calculation(
+        &self, score_v1: f64, judgements: &Judgements, combo_ratio: f64, spinner_bonus: f64,
+        estimated: bool,
+    ) -> ScoreCalculation
+    {
+        let (combo_portion, accuracy_portion) = self.score_v2(judgements, combo_ratio);
+        ScoreCalculation {
+            score_v1: score_v1.round() as u32,
+            score_v2: ((combo_portion + accuracy_portion) * self.score_v2_multiplier
+                + spinner_bonus)
+                .round() as u32,
+            combo_portion,
+            accuracy_portion,
+            spinner_bonus,
+            estimated,
+        }
+    }
+}
+
+impl ReplayJudgements
+{
+    /// Calculates ScoreV1 and ScoreV2 from the judgement of every object.
+    ///
+    /// Slider ticks and repeats only have hit counts, so missed ones are assumed to come first.
+    pub fn score(&self, beatmap: &ParserBeatmap, mods: Mods) -> ParserResult<ScoreCalculation>
+    {
+        let context = ScoreContext::new(beatmap, mods)?;
+        let hit_objects = beatmap
+            .hit_objects
+            .as_ref()
+            .ok_or(ParserError::HitobjectsMissing)?;
+
+        let mut combo = 0.0;
+        let mut score_v1 = 0.0;
+        let mut combo_score_v2 = 0.0;
+        let mut spinner_bonus = 0.0;
+        for object in &self.objects
+        {
+            if let Some(slider) = object.slider
+            {
+                if slider.head_hit
+                {
+                    score_v1 += SLIDER_EDGE_SCORE;
+                    combo += 1.0;
+                }
+                else
+                {
+                    combo = 0.0;
+                }
+                if slider.ticks_hit < slider.ticks_total
+                    || slider.repeats_hit < slider.repeats_total
+                {
+                    combo = 0.0;
+                }
+                score_v1 += slider.ticks_hit as f64 * SLIDER_TICK_SCORE
+                    + slider.repeats_hit as f64 * SLIDER_EDGE_SCORE;
+                combo += (slider.ticks_hit + slider.repeats_hit) as f64;
+                // Missing a slider end does not break combo
+                if slider.end_hit
+                {
+                    score_v1 += SLIDER_EDGE_SCORE;
+                    combo += 1.0;
+                }
+                score_v1 += context.hit_score(hit_value(object.result), combo);
+                combo_score_v2 += combo_score(hit_value(object.result), combo);
+                continue;
+            }
+
+            if let (Some(rotations), Some(HitObjectKind::Spinner { end_time })) = (
+                object.spinner_rotations,
+                hit_objects.get(object.object_index).map(|x| &x.kind),
+            )
+            {
+                let required = spins_required(beatmap, mods, end_time - object.time);
+                let spins = rotations.floor();
+                score_v1 += spins.min(required) * SPINNER_SPIN_SCORE;
+                let bonus = (spins - required).max(0.0) * SPINNER_BONUS_SCORE;
+                score_v1 += bonus;
+                spinner_bonus += bonus;
+            }
+            if object.result == HitResult::Miss
+            {
+                combo = 0.0;
+                continue;
+            }
+            score_v1 += context.hit_score(hit_value(object.result), combo);
+            combo += 1.0;
+            combo_score_v2 += combo_score(hit_value(object.result), combo);
+        }
+
+        let combo_ratio = if context.max_combo_score > 0.0
+        {
+            combo_score_v2 / context.max_combo_score
+        }
+        else
+        {
+            0.0
+        };
+        Ok(context.calculation(
+            score_v1,
+            &self.judgements,
+            combo_ratio,
+            spinner_bonus,
+            false,
+        ))
+    }
+}
+
+impl Judgements
+{
+    /// Estimates ScoreV1 and ScoreV2 from judgement counts and the max combo of a play.
+    ///
+    /// Both scores depend on the combo at every hit, which the counts do not have. Hits are
+    /// assumed to have the average hit value and to form streaks of `max_combo`, and sliders to
+    /// be hit fully in proportion to the hit objects, which overestimates plays that broke combo
+    /// more often. Only the ScoreV2 accuracy portion is exact, spinner bonus is left out.
+    ///
+    /// The estimate cannot settle desyncs, use [`ReplayJudgements::score`] with the judgements of
+    /// the replay for that.
+    pub fn score(
+        &self, max_combo: u32, beatmap: &ParserBeatmap, mods: Mods,
+    ) -> ParserResult<ScoreCalculation>
+    {
+        let context = ScoreContext::new(beatmap, mods)?;
+        let (values, judged) = judged_values(self);
+        let hits = judged - self.miss.unwrap_or(0) as f64;
+        let mut score_v1 = 0.0;
+        let mut combo_ratio = 0.0;
+        if hits > 0.0 && context.objects > 0
+        {
+            let average_value = values / hits;
+            let combo_per_object = context.max_combo / context.objects as f64;
+            let streak = (max_combo as f64 / combo_per_object).round().max(1.0) as usize;
+            let (mut combo_score_v2, mut max_combo_score) = (0.0, 0.0);
+            for hit in 0..hits as usize
+            {
+                let combo = (hit % streak) as f64 * combo_per_object;
+                score_v1 += context.hit_score(average_value, combo);
+                combo_score_v2 += combo_score(average_value, combo + combo_per_object);
+            }
+            // Compared with a full combo under the same even spread of slider combo
+            for object in 1..=context.objects
+            {
+                max_combo_score += combo_score(300.0, object as f64 * combo_per_object);
+            }
+            score_v1 += context.slider_score * hits / context.objects as f64;
+            combo_ratio = combo_score_v2 / max_combo_score;
+        }
+        Ok(context.calculation(score_v1, self, combo_ratio, 0.0, true))
+    }
+}
+
+impl Replay
+{
+    /// ScoreV1 and ScoreV2 of the simulated judgements, see [`Replay::simulate_judgements`].
+    pub fn calculate_score(&self, beatmap: &ParserBeatmap) -> ParserResult<ScoreCalculation>
+    {
+        self.simulate_judgements(beatmap)?.score(beatmap, self.mods)
+    }
+}
+
+fn hit_value(result: HitResult) -> f64
+{
+    match result
+    {
+        HitResult::Hit300 => 300.0,
+        HitResult::Hit100 => 100.0,
+        HitResult::Hit50 => 50.0,
+        HitResult::Miss => 0.0,
+    }
+}
+
+/// ScoreV2 weight of a hit value from the combo after the hit.
+fn combo_score(value: f64, combo: f64) -> f64 { value * (1.0 + combo / SCORE_V2_COMBO_DIVISOR) }
+
+/// Sum of the hit values and number of judged objects.
+fn judged_values(judgements: &Judgements) -> (f64, f64)
+{
+    let count = |count: Option<u16>| count.unwrap_or(0) as f64;
+    let values = count(judgements.count_300) * 300.0
+        + count(judgements.count_100) * 100.0
+        + count(judgements.count_50) * 50.0;
+    let judged = count(judgements.count_300)
+        + count(judgements.count_100)
+        + count(judgements.count_50)
+        + count(judgements.miss);
+    (values, judged)
+}
+
+/// Difficulty multiplier of ScoreV1 from the unmodded difficulty and object density.
+///
+/// Like osu! stable, the drain length is truncated to whole seconds and a drain length of 0 counts
+/// as the highest density.
+fn difficulty_multiplier(beatmap: &ParserBeatmap) -> f64
+{
+    let hit_objects = beatmap.hit_objects.as_deref().unwrap_or_default();
+    let (first, last) = match (hit_objects.first(), hit_objects.last())
+    {
+        (Some(first), Some(last)) => (first.start_time, last.start_time),
+        _ => return 0.0,
+    };
+    let break_length = beatmap
+        .breaks
+        .iter()
+        .flatten()
+        .map(|break_period| break_period.end_time as i64 - break_period.start_time as i64)
+        .sum::<i64>();
+    let drain_length = (last.round() as i64 - first.round() as i64 - break_length) / 1000;
+    let density = if drain_length != 0
+    {
+        (hit_objects.len() as f64 / drain_length as f64 * 8.0).clamp(0.0, 16.0)
+    }
+    else
+    {
+        16.0
+    };
+
+    (((beatmap.hp + beatmap.od + beatmap.cs) as f64 + density) / 38.0 * 5.0).round()
+}
+
+fn score_v1_mod_multiplier(mods: Mods) -> f64 { mod_multiplier(mods, 0.5, 1.06, 1.12) }
+
+fn score_v2_mod_multiplier(mods: Mods) -> f64 { mod_multiplier(mods, 1.0, 1.10, 1.20) }
+
+fn mod_multiplier(mods: Mods, no_fail: f64, hard_rock: f64, double_time: f64) -> f64
+{
+    if mods.intersects(Mods::Relax | Mods::Relax2 | Mods::Autoplay)
+    {
+        return 0.0;
+    }
+    [
+        (Mods::NoFail, no_fail),
+        (Mods::Easy, 0.5),
+        (Mods::HalfTime, 0.3),
+        (Mods::Hidden, 1.06),
+        (Mods::HardRock, hard_rock),
+        (Mods::DoubleTime | Mods::Nightcore, double_time),
+        (Mods::Flashlight, 1.12),
+        (Mods::SpunOut, 0.9),
+    ]
+    .iter()
+    .filter(|(mod_flags, _)| mods.intersects(*mod_flags))
+    .map(|(_, multiplier)| multiplier)
+    .product()
+}
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{hit_errors::mean, Buttons, Mods, ParserError, ParserResult, Replay, ReplayFrame};
+use crate::beatmap::objects::Pos2;
+
+/// Interval in ms of beatmap time the cursor positions are compared at.
+const SAMPLE_INTERVAL: i32 = 10;
+/// Presses of both replays closer than this many ms are matched.
+const PRESS_MATCH_WINDOW: f64 = 50.0;
+
+/// Similarity of the cursor movement and key presses of two replays of the same beatmap.
+///
+/// Copied replays usually have a mean distance below 20 osu!pixels and presses within a few ms of
+/// each other, while two players rarely get below 50 osu!pixels.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplaySimilarity
+{
+    /// Mean distance between both cursors in osu!pixels.
+    pub mean_distance:         f64,
+    /// Pearson correlation of the cursor positions, averaged over both axes.
+    pub correlation:           f64,
+    /// Whether the other replay was flipped to correct a HR difference.
+    pub flipped:               bool,
+    /// Number of positions compared.
+    pub samples:               u32,
+    /// Share of presses with a press of the other replay within 50ms.
+    pub matched_presses:       f64,
+    /// Mean time between matched presses in ms.
+    pub press_time_difference: f64,
+}
+
+impl Replay
+{
+    /// Compares the cursor movement and key presses of two replays of the same beatmap.
+    ///
+    /// With `correct_flip`, the other replay is flipped vertically if only one of them has HR.
+    /// Requires [`Replay::replay_frame_data`] of both replays, see [`Replay::decode_frames`].
+    pub fn similarity(&self, other: &Replay, correct_flip: bool) -> ParserResult<ReplaySimilarity>
+    {
+        if self.beatmap_hash != other.beatmap_hash
+        {
+            return Err(ParserError::ReplayBeatmapMismatch(
+                self.beatmap_hash.clone(),
+                other.beatmap_hash.clone(),
+            ));
+        }
+        let (frames, other_frames) = (gameplay_frames(self)?, gameplay_frames(other)?);
+        let flipped = correct_flip
+            && self.mods.contains(Mods::HardRock) != other.mods.contains(Mods::HardRock);
+
+        let path = cursor_path(frames, false);
+        let other_path = cursor_path(other_frames, flipped);
+        let (mut distances, mut positions, mut other_positions) = (vec![], vec![], vec![]);
+        if let (Some(first), Some(other_first), Some(last), Some(other_last)) = (
+            path.first(),
+            other_path.first(),
+            path.last(),
+            other_path.last(),
+        )
+        {
+            let (mut index, mut other_index) = (0, 0);
+            let mut time = first.0.max(other_first.0);
+            while time <= last.0.min(other_last.0)
+            {
+                let pos = position_at(&path, &mut index, time);
+                let other_pos = position_at(&other_path, &mut other_index, time);
+                distances.push(pos.distance(other_pos) as f64);
+                positions.push(pos);
+                other_positions.push(other_pos);
+                time += SAMPLE_INTERVAL;
+            }
+        }
+        let axis = |positions: &[Pos2], x: bool| {
+            positions
+                .iter()
+                .map(|pos| if x { pos.x } else { pos.y } as f64)
+                .collect::<Vec<_>>()
+        };
+        let correlation = (correlation(&axis(&positions, true), &axis(&other_positions, true))
+            + correlation(&axis(&positions, false), &axis(&other_positions, false)))
+            / 2.0;
+
+        let presses = press_times(frames);
+        let mut other_presses = press_times(other_frames);
+        other_presses.sort_by(|a, b| a.total_cmp(b));
+        let differences = presses
+            .iter()
+            .filter_map(|time| {
+                // Nearest press is right before or after the insertion point
+                let index = other_presses.partition_point(|other_time| other_time < time);
+                other_presses[index.saturating_sub(1)..other_presses.len().min(index + 1)]
+                    .iter()
+                    .map(|other_time| (time - other_time).abs())
+                    .min_by(|a, b| a.total_cmp(b))
+                    .filter(|difference| *difference <= PRESS_MATCH_WINDOW)
+            })
+            .collect::<Vec<_>>();
+        let total_presses = presses.len().max(other_presses.len());
+
+        Ok(ReplaySimilarity {
+            mean_distance: mean(&distances),
+            correlation,
+            flipped,
+            samples: distances.len() as u32,
+            matched_presses: if total_presses > 0
+            {
+                differences.len() as f64 / total_presses as f64
+            }
+            else
+            {
+                0.0
+            },
+            press_time_difference: mean(&differences),
+        })
+    }
+}
+
+fn gameplay_frames(replay: &Replay) -> ParserResult<&[ReplayFrame]>
+{
+    Ok(replay
+        .replay_frame_data
+        .as_ref()
+        .ok_or(ParserError::ReplayFramesMissing)?
+        .gameplay_frames())
+}
+
+/// Cursor positions with strictly increasing times, frames going back in time are dropped.
+fn cursor_path(frames: &[ReplayFrame], flip: bool) -> Vec<(i32, Pos2)>
+{
+    let mut path: Vec<(i32, Pos2)> = Vec::with_capacity(frames.len());
+    for frame in frames
+    {
+        if path.last().is_some_and(|last| frame.timestamp_ms <= last.0)
+        {
+            continue;
+        }
+        let pos = if flip
+        {
+            frame.cursor_pos.flip_vertically()
+        }
+        else
+        {
+            frame.cursor_pos
+        };
+        path.push((frame.timestamp_ms, pos));
+    }
+    path
+}
+
+/// Linearly interpolated cursor position, `index` is advanced for increasing times.
+fn position_at(path: &[(i32, Pos2)], index: &mut usize, time: i32) -> Pos2
+{
+    while *index + 1 < path.len() && path[*index + 1].0 <= time
+    {
+        *index += 1;
+    }
+    let (start_time, start) = path[*index];
+    match path.get(*index + 1)
+    {
+        Some((end_time, end)) if time > start_time =>
+        {
+            let progress = (time - start_time) as f32 / (end_time - start_time) as f32;
+            start + (*end - start) * progress
+        }
+        _ => start,
+    }
+}
+
+/// Times of every key and mouse button press, keys also set their mouse button.
+fn press_times(frames: &[ReplayFrame]) -> Vec<f64>
+{
+    let mut previous = Buttons::empty();
+    let mut presses = Vec::new();
+    for frame in frames
+    {
+        let pressed = frame.buttons & !previous & (Buttons::M1 | Buttons::M2);
+        for _ in 0..pressed.bits().count_ones()
+        {
+            presses.push(frame.timestamp_ms as f64);
+        }
+        previous = frame.buttons;
+    }
+    presses
+}
+
+fn correlation(a: &[f64], b: &[f64]) -> f64
+{
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (a, b) in a.iter().zip(b)
+    {
+        covariance += (a - mean_a) * (b - mean_b);
+        variance_a += (a - mean_a).powi(2);
+        variance_b += (b - mean_b).powi(2);
+    }
+    if variance_a == 0.0 || variance_b == 0.0
+    {
+        0.0
+    }
+    else
+    {
+        covariance / (variance_a * variance_b).sqrt()
+    }
+}
@@ -0,0 +1,316 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    hit_errors::mean, Buttons, Mods, ParserError, ParserResult, Replay, ReplayFrame,
+    ReplayFrameData,
+};
+
+/// Time since the previous frame of the frame storing the RNG seed.
+const SEED_FRAME_TIME: i32 = -12345;
+/// Number of presses the tapping BPM is averaged over.
+const TAPPING_BPM_WINDOW: usize = 8;
+/// Presses further apart than this many ms are not part of the same tapping section.
+const TAPPING_MAX_INTERVAL: f64 = 1000.0;
+/// Forward jumps longer than this many ms are reported as skips.
+const SKIP_THRESHOLD: i32 = 1000;
+/// Frames without time passing in a row which are reported as a pause.
+const PAUSE_MIN_FRAMES: usize = 10;
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct KeyStats
+{
+    pub presses:             u32,
+    /// Duration of every press in ms.
+    pub press_durations:     Vec<f64>,
+    pub mean_press_duration: f64,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct TappingBpm
+{
+    pub time: f64,
+    /// BPM of 1/4 notes with the same interval as the last presses.
+    pub bpm:  f64,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct FrameTimeStats
+{
+    pub mean:     f64,
+    pub median:   f64,
+    pub std_dev:  f64,
+    pub min:      f64,
+    pub max:      f64,
+    /// Frames taking more than twice the median time.
+    pub outliers: u32,
+}
+
+/// Forward jump in time between two frames.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplaySkip
+{
+    pub start_time: i32,
+    pub end_time:   i32,
+}
+
+/// Frames recorded while the game time did not advance.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplayPause
+{
+    pub time:   i32,
+    pub frames: u32,
+}
+
+/// Key and cursor statistics of a replay.
+///
+/// Times of events are in beatmap time, while durations, BPM and speed are in real time, meaning
+/// they are corrected for the DT/HT clock rate.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ReplayStats
+{
+    pub k1:                   KeyStats,
+    pub k2:                   KeyStats,
+    /// Mouse buttons only count when the matching key is not held, as keys also set them.
+    pub m1:                   KeyStats,
+    pub m2:                   KeyStats,
+    pub total_presses:        u32,
+    pub tapping_bpm:          Vec<TappingBpm>,
+    /// Distance travelled by the cursor in osu!pixels.
+    pub cursor_distance:      f64,
+    /// Average cursor speed in osu!pixels per second.
+    pub average_cursor_speed: f64,
+    pub smoke_frames:         u32,
+    pub frame_times:          FrameTimeStats,
+    /// Skipped intros and outros.
+    pub skips:                Vec<ReplaySkip>,
+    pub pauses:               Vec<ReplayPause>,
+}
+
+impl ReplayFrameData
+{
+    /// Frames recorded during gameplay, without the seed frame and the two frames osu! stable
+    /// places at (256, -500) at the start of every replay.
+    pub fn gameplay_frames(&self) -> &[ReplayFrame] { &self.frames[self.gameplay_range()] }
+
+    /// Range of [`ReplayFrameData::gameplay_frames`] in [`ReplayFrameData::frames`].
+    pub fn gameplay_range(&self) -> Range<usize>
+    {
+        let start = self
+            .frames
+            .iter()
+            .take(2)
+            .take_while(|frame| frame.cursor_pos.x == 256.0 && frame.cursor_pos.y == -500.0)
+            .count();
+        let end = match self.frames.last()
+        {
+            Some(frame) if frame.time_since_ms == SEED_FRAME_TIME => self.frames.len() - 1,
+            _ => self.frames.len(),
+        };
+        start..end.max(start)
+    }
+
+    pub fn stats(&self, mods: Mods) -> ReplayStats
+    {
+        let clock_rate = mods.clock_rate();
+        let frames = self.gameplay_frames();
+
+        // Keys also set their mouse button, so mouse buttons held with their key are ignored
+        let key_states = |frame: &ReplayFrame| {
+            let buttons = frame.buttons;
+            [
+                buttons.contains(Buttons::K1),
+                buttons.contains(Buttons::K2),
+                buttons.contains(Buttons::M1) && !buttons.contains(Buttons::K1),
+                buttons.contains(Buttons::M2) && !buttons.contains(Buttons::K2),
+            ]
+        };
+        let mut press_starts: [Option<i32>; 4] = [None; 4];
+        let mut durations: [Vec<f64>; 4] = Default::default();
+        let mut presses = Vec::new();
+        for frame in frames
+        {
+            for (key, held) in key_states(frame).iter().enumerate()
+            {
+                match (press_starts[key], *held)
+                {
+                    (None, true) =>
+                    {
+                        press_starts[key] = Some(frame.timestamp_ms);
+                        presses.push(frame.timestamp_ms as f64);
+                    }
+                    (Some(start), false) =>
+                    {
+                        durations[key].push((frame.timestamp_ms - start) as f64 / clock_rate);
+                        press_starts[key] = None;
+                    }
+                    _ =>
+                    {}
+                }
+            }
+        }
+        // Presses still held on the last frame end with it
+        if let Some(last) = frames.last()
+        {
+            for (key, start) in press_starts.iter().enumerate()
+            {
+                if let Some(start) = start
+                {
+                    durations[key].push((last.timestamp_ms - start) as f64 / clock_rate);
+                }
+            }
+        }
+        let [k1, k2, m1, m2] = durations;
+
+        let tapping_bpm = presses
+            .windows(TAPPING_BPM_WINDOW + 1)
+            .filter(|window| {
+                window
+                    .windows(2)
+                    .all(|pair| pair[1] - pair[0] <= TAPPING_MAX_INTERVAL)
+            })
+            .filter_map(|window| {
+                let last = *window.last()?;
+                let interval = (last - window[0]) / TAPPING_BPM_WINDOW as f64 / clock_rate;
+                (interval > 0.0).then(|| TappingBpm {
+                    time: last,
+                    bpm:  15000.0 / interval,
+                })
+            })
+            .collect();
+
+        let cursor_distance = frames
+            .windows(2)
+            .map(|pair| pair[0].cursor_pos.distance(pair[1].cursor_pos) as f64)
+            .sum::<f64>();
+        let skips = frames
+            .iter()
+            .filter(|frame| frame.time_since_ms > SKIP_THRESHOLD)
+            .map(|frame| ReplaySkip {
+                start_time: frame.timestamp_ms - frame.time_since_ms,
+                end_time:   frame.timestamp_ms,
+            })
+            .collect();
+        let mut pauses = Vec::new();
+        let mut frozen = 0;
+        for (index, frame) in frames.iter().enumerate()
+        {
+            if frame.time_since_ms == 0
+            {
+                frozen += 1;
+            }
+            if frame.time_since_ms != 0 || index == frames.len() - 1
+            {
+                if frozen >= PAUSE_MIN_FRAMES
+                {
+                    pauses.push(ReplayPause {
+                        time:   frames[index - 1].timestamp_ms,
+                        frames: frozen as u32,
+                    });
+                }
+                frozen = 0;
+            }
+        }
+
+        // Skips and frames going back in time are not part of the frame rate
+        let frame_times = frames
+            .iter()
+            .filter(|frame| (0..=SKIP_THRESHOLD).contains(&frame.time_since_ms))
+            .map(|frame| frame.time_since_ms as f64 / clock_rate)
+            .collect::<Vec<_>>();
+        let duration = frame_times.iter().sum::<f64>();
+
+        ReplayStats {
+            total_presses: presses.len() as u32,
+            k1: KeyStats::new(k1),
+            k2: KeyStats::new(k2),
+            m1: KeyStats::new(m1),
+            m2: KeyStats::new(m2),
+            tapping_bpm,
+            cursor_distance,
+            average_cursor_speed: if duration > 0.0
+            {
+                cursor_distance / duration * 1000.0
+            }
+            else
+            {
+                0.0
+            },
+            smoke_frames: frames
+                .iter()
+                .filter(|frame| frame.buttons.contains(Buttons::SMOKE))
+                .count() as u32,
+            frame_times: FrameTimeStats::new(&frame_times),
+            skips,
+            pauses,
+        }
+    }
+}
+
+impl KeyStats
+{
+    fn new(press_durations: Vec<f64>) -> Self
+    {
+        Self {
+            presses: press_durations.len() as u32,
+            mean_press_duration: mean(&press_durations),
+            press_durations,
+        }
+    }
+}
+
+impl FrameTimeStats
+{
+    /// Statistics of frame times in ms.
+    pub fn new(frame_times: &[f64]) -> Self
+    {
+        if frame_times.is_empty()
+        {
+            return Self::default();
+        }
+
+        let mut sorted = frame_times.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        // Average of the two middle values for even lengths, the middle value otherwise
+        let median = (sorted[(sorted.len() - 1) / 2] + sorted[sorted.len() / 2]) / 2.0;
+        let mean = mean(frame_times);
+        let variance = frame_times
+            .iter()
+            .map(|time| (time - mean).powi(2))
+            .sum::<f64>()
+            / frame_times.len() as f64;
+
+        Self {
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            outliers: frame_times
+                .iter()
+                .filter(|time| **time > median * 2.0)
+                .count() as u32,
+        }
+    }
+}
+
+impl Replay
+{
+    /// Key and cursor statistics of the replay frames, see [`Replay::decode_frames`].
+    pub fn stats(&self) -> ParserResult<ReplayStats>
+    {
+        Ok(self
+            .replay_frame_data
+            .as_ref()
+            .ok_or(ParserError::ReplayFramesMissing)?
+            .stats(self.mods))
+    }
+}
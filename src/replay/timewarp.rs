@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use super::{
+    hit_errors::mean, stats::FrameTimeStats, Mods, ParserError, ParserResult, Replay,
+    ReplayFrameData,
+};
+
+/// Time between frames osu! stable records at without rate changing mods, in ms.
+const EXPECTED_FRAME_TIME: f64 = 1000.0 / 60.0;
+/// Relative deviation from the expected frame time which is not suspicious.
+const TOLERANCE: f64 = 0.1;
+/// Relative deviation from the expected frame time which is fully suspicious.
+const MAX_DEVIATION: f64 = 0.3;
+/// Number of frames each section of the replay is checked over.
+const WINDOW_FRAMES: usize = 400;
+/// Frames further apart than this many ms are skips and not part of the frame rate.
+const MAX_FRAME_TIME: i32 = 1000;
+
+/// Section of the replay with frame times inconsistent with its mods.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct TimewarpEvidence
+{
+    pub start_time:          i32,
+    pub end_time:            i32,
+    pub frames:              u32,
+    /// Median frame time of the section multiplied by the clock rate of the mods.
+    pub median_frame_time:   f64,
+    /// Clock rate the section was played at if it was recorded at 60 fps.
+    pub apparent_clock_rate: f64,
+    pub suspicion:           f64,
+}
+
+/// Comparison of the replay frame times with the clock rate of its mods.
+///
+/// osu! stable records frames about every 16.67ms, divided by the clock rate of the mods, so frame
+/// times are about 16.67ms for NoMod, 11.1ms for DT and 22.2ms for HT. Slowing the game down makes
+/// them longer than the mods allow.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct TimewarpReport
+{
+    /// Clock rate of the replay mods.
+    pub clock_rate:          f64,
+    /// Frame times multiplied by the clock rate of the mods, about 16.67ms for every mod.
+    pub frame_times:         FrameTimeStats,
+    /// Clock rate the replay was played at if it was recorded at 60 fps.
+    pub apparent_clock_rate: f64,
+    /// Between 0 and 1, where 0 means the frame times match the mods.
+    pub suspicion:           f64,
+    pub evidence:            Vec<TimewarpEvidence>,
+}
+
+impl ReplayFrameData
+{
+    pub fn timewarp(&self, mods: Mods) -> TimewarpReport
+    {
+        let clock_rate = mods.clock_rate();
+        let frames = self
+            .gameplay_frames()
+            .iter()
+            .filter(|frame| (1..=MAX_FRAME_TIME).contains(&frame.time_since_ms))
+            .collect::<Vec<_>>();
+        let frame_times = frames
+            .iter()
+            .map(|frame| frame.time_since_ms as f64 * clock_rate)
+            .collect::<Vec<_>>();
+        let stats = FrameTimeStats::new(&frame_times);
+        if frames.len() < WINDOW_FRAMES
+        {
+            return TimewarpReport {
+                clock_rate,
+                frame_times: stats,
+                ..Default::default()
+            };
+        }
+
+        let sections = frames
+            .chunks(WINDOW_FRAMES)
+            .zip(frame_times.chunks(WINDOW_FRAMES))
+            .filter(|(window, _)| window.len() == WINDOW_FRAMES)
+            .map(|(window, times)| {
+                let median = FrameTimeStats::new(times).median;
+                TimewarpEvidence {
+                    start_time:          window[0].timestamp_ms - window[0].time_since_ms,
+                    end_time:            window[window.len() - 1].timestamp_ms,
+                    frames:              window.len() as u32,
+                    median_frame_time:   median,
+                    apparent_clock_rate: apparent_clock_rate(median, clock_rate),
+                    suspicion:           suspicion(median),
+                }
+            })
+            .collect::<Vec<_>>();
+        // Every section has the same number of frames, so they count equally and a short anomaly
+        // is averaged out. Frames after the last full section only count towards the overall median
+        let section_suspicion = mean(
+            &sections
+                .iter()
+                .map(|section| section.suspicion)
+                .collect::<Vec<_>>(),
+        );
+
+        TimewarpReport {
+            clock_rate,
+            apparent_clock_rate: apparent_clock_rate(stats.median, clock_rate),
+            suspicion: suspicion(stats.median).max(section_suspicion),
+            frame_times: stats,
+            evidence: sections
+                .into_iter()
+                .filter(|section| section.suspicion > 0.0)
+                .collect(),
+        }
+    }
+}
+
+/// Suspicion of a median frame time multiplied by the clock rate of the mods.
+fn suspicion(median_frame_time: f64) -> f64
+{
+    let deviation = (median_frame_time / EXPECTED_FRAME_TIME - 1.0).abs();
+    ((deviation - TOLERANCE) / (MAX_DEVIATION - TOLERANCE)).clamp(0.0, 1.0)
+}
+
+/// Clock rate the frames were recorded at, from the median frame time multiplied by the clock rate
+/// of the mods.
+fn apparent_clock_rate(median_frame_time: f64, clock_rate: f64) -> f64
+{
+    EXPECTED_FRAME_TIME * clock_rate / median_frame_time
+}
+
+impl Replay
+{
+    /// Checks whether the frame times of the replay match its mods, see
+    /// [`Replay::decode_frames`].
+    pub fn timewarp(&self) -> ParserResult<TimewarpReport>
+    {
+        Ok(self
+            .replay_frame_data
+            .as_ref()
+            .ok_or(ParserError::ReplayFramesMissing)?
+            .timewarp(self.mods))
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::Write;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use lzma_rs::compress::{Options, UnpackedSize};
+
+use super::{parse::LEBResult, LifegraphData, Mods, ParserResult, Replay, ReplayFrame};
+
+pub trait ULEB128Encode: Write
+{
+    const LEB128_HIGH_ORDER_BIT: u8 = 1 << 7;
+
+    fn write_uleb128(&mut self, mut value: u64) -> LEBResult<()>
+    {
+        loop
+        {
+            let byte = (value & !(Self::LEB128_HIGH_ORDER_BIT as u64)) as u8;
+            value >>= 7;
+            if value == 0
+            {
+                self.write_u8(byte)?;
+                return Ok(());
+            }
+            self.write_u8(byte | Self::LEB128_HIGH_ORDER_BIT)?;
+        }
+    }
+
+    fn write_uleb128_string(&mut self, str: &str) -> LEBResult<()>
+    {
+        if str.is_empty()
+        {
+            self.write_u8(0x0)?;
+            return Ok(());
+        }
+        self.write_u8(0xB)?;
+        self.write_uleb128(str.len() as u64)?;
+        self.write_all(str.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<W: Write + ?Sized> ULEB128Encode for W {}
+
+impl Replay
+{
+    /// Writes the replay in the .osr format.
+    ///
+    /// Frames from [`Replay::replay_frame_data`] are re-encoded and compressed when present,
+    /// otherwise the raw [`Replay::replay_data`] is written back untouched.
+    pub fn write<W: Write>(&self, out: &mut W) -> ParserResult<()>
+    {
+        out.write_u8(self.mode as u8)?;
+        out.write_u32::<LittleEndian>(self.version)?;
+        out.write_uleb128_string(&self.beatmap_hash)?;
+        out.write_uleb128_string(&self.username)?;
+        out.write_uleb128_string(&self.replay_hash)?;
+        out.write_u16::<LittleEndian>(self.judgements.count_300.unwrap_or(0))?;
+        out.write_u16::<LittleEndian>(self.judgements.count_100.unwrap_or(0))?;
+        out.write_u16::<LittleEndian>(self.judgements.count_50.unwrap_or(0))?;
+        out.write_u16::<LittleEndian>(self.judgements.count_geki.unwrap_or(0))?;
+        out.write_u16::<LittleEndian>(self.judgements.count_katu.unwrap_or(0))?;
+        out.write_u16::<LittleEndian>(self.judgements.miss.unwrap_or(0))?;
+        out.write_u32::<LittleEndian>(self.score)?;
+        out.write_u16::<LittleEndian>(self.max_combo)?;
+        out.write_u8(self.perfect as u8)?;
+        out.write_u32::<LittleEndian>(self.mods.bits())?;
+        out.write_uleb128_string(&encode_life_graph(&self.life_graph))?;
+        out.write_u64::<LittleEndian>(self.timestamp.parse::<u64>()?)?;
+
+        let replay_data = match &self.replay_frame_data
+        {
+            Some(frame_data) => compress_frames(&frame_data.frames)?,
+            None => self.replay_data.clone().unwrap_or_default(),
+        };
+        out.write_u32::<LittleEndian>(replay_data.len() as u32)?;
+        out.write_all(&replay_data)?;
+
+        let score_id = match &self.score_id
+        {
+            Some(id) => id.parse::<u64>()?,
+            None => 0,
+        };
+        out.write_u64::<LittleEndian>(score_id)?;
+
+        if self.mods.contains(Mods::TargetPractice)
+        {
+            out.write_f64::<LittleEndian>(self.target_practice_accuracy.unwrap_or_default())?;
+        }
+        if let Some(score_info) = &self.lazer_score_info
+        {
+            let score_info = score_info.compress()?;
+            out.write_i32::<LittleEndian>(score_info.len() as i32)?;
+            out.write_all(&score_info)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn encode_life_graph(life_graph: &[LifegraphData]) -> String
+{
+    life_graph
+        .iter()
+        .map(|point| format!("{}|{},", point.time, point.life_value))
+        .collect()
+}
+
+fn compress_frames(frames: &[ReplayFrame]) -> ParserResult<Vec<u8>>
+{
+    let decoded_data = frames
+        .iter()
+        .map(|frame| {
+            format!(
+                "{}|{}|{}|{},",
+                frame.time_since_ms,
+                frame.cursor_pos.x,
+                frame.cursor_pos.y,
+                frame.buttons.bits()
+            )
+        })
+        .collect::<String>();
+
+    let mut encoded_data = Vec::new();
+    lzma_rs::lzma_compress_with_options(
+        &mut decoded_data.as_bytes(),
+        &mut encoded_data,
+        &Options {
+            unpacked_size: UnpackedSize::WriteToHeader(Some(decoded_data.len() as u64)),
+        },
+    )?;
+    Ok(encoded_data)
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use crate::beatmap::Color;
+
+#[derive(Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinGeneral
+{
+    pub name:                            String,
+    pub author:                          String,
+    /// Skin version, `latest` or a number like `2.7`.
+    pub version:                         String,
+    pub animation_framerate:             Option<f32>,
+    pub allow_slider_ball_tint:          bool,
+    pub combo_burst_random:              bool,
+    pub cursor_centre:                   bool,
+    pub cursor_expand:                   bool,
+    pub cursor_rotate:                   bool,
+    pub cursor_trail_rotate:             bool,
+    pub hit_circle_overlay_above_number: bool,
+    pub layered_hit_sounds:              bool,
+    pub slider_ball_flip:                bool,
+    pub spinner_fade_playfield:          bool,
+    pub spinner_no_blink:                bool,
+}
+
+impl Default for SkinGeneral
+{
+    fn default() -> Self
+    {
+        Self {
+            name:                            String::new(),
+            author:                          String::new(),
+            version:                         String::from("1.0"),
+            animation_framerate:             None,
+            allow_slider_ball_tint:          false,
+            combo_burst_random:              false,
+            cursor_centre:                   true,
+            cursor_expand:                   true,
+            cursor_rotate:                   true,
+            cursor_trail_rotate:             true,
+            hit_circle_overlay_above_number: true,
+            layered_hit_sounds:              true,
+            slider_ball_flip:                true,
+            spinner_fade_playfield:          false,
+            spinner_no_blink:                false,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinColours
+{
+    pub combo_colours:             Vec<Color>,
+    pub slider_border:             Option<Color>,
+    pub slider_track_override:     Option<Color>,
+    pub slider_ball:               Option<Color>,
+    pub spinner_background:        Option<Color>,
+    pub menu_glow:                 Option<Color>,
+    pub song_select_active_text:   Option<Color>,
+    pub song_select_inactive_text: Option<Color>,
+    pub star_break_additive:       Option<Color>,
+    pub input_overlay_text:        Option<Color>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Tsify, Clone)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinFonts
+{
+    pub hit_circle_prefix:  String,
+    pub hit_circle_overlap: i32,
+    pub score_prefix:       String,
+    pub score_overlap:      i32,
+    pub combo_prefix:       String,
+    pub combo_overlap:      i32,
+}
+
+impl Default for SkinFonts
+{
+    fn default() -> Self
+    {
+        Self {
+            hit_circle_prefix:  String::from("default"),
+            hit_circle_overlap: -2,
+            score_prefix:       String::from("score"),
+            score_overlap:      0,
+            combo_prefix:       String::from("score"),
+            combo_overlap:      0,
+        }
+    }
+}
+
+/// Mania configuration of a key count.
+///
+/// Mania sections have too many keys to be typed individually, so they are kept as raw values.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinMania
+{
+    pub keys:   u8,
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinIni
+{
+    pub general: SkinGeneral,
+    pub colours: SkinColours,
+    pub fonts:   SkinFonts,
+    pub mania:   Vec<SkinMania>,
+}
+
+impl SkinIni
+{
+    pub fn parse(text: &str) -> Self
+    {
+        let mut ini = Self::default();
+        let mut section = String::new();
+
+        for line in text.trim_start_matches('\u{feff}').lines()
+        {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//")
+            {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']')
+            {
+                section = line[1..line.len() - 1].trim().to_string();
+                if section == "Mania"
+                {
+                    ini.mania.push(SkinMania::default());
+                }
+                continue;
+            }
+            let (key, value) = match line.split_once(':')
+            {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+
+            match section.as_str()
+            {
+                "General" => ini.general.set(key, value),
+                "Colours" => ini.colours.set(key, value),
+                "Fonts" => ini.fonts.set(key, value),
+                "Mania" =>
+                {
+                    let mania = ini.mania.last_mut().unwrap();
+                    if key == "Keys"
+                    {
+                        mania.keys = value.parse().unwrap_or_default();
+                    }
+                    else
+                    {
+                        mania.values.insert(key.to_string(), value.to_string());
+                    }
+                }
+                _ =>
+                {}
+            }
+        }
+
+        ini
+    }
+}
+
+impl SkinGeneral
+{
+    fn set(&mut self, key: &str, value: &str)
+    {
+        match key
+        {
+            "Name" => self.name = value.to_string(),
+            "Author" => self.author = value.to_string(),
+            "Version" => self.version = value.to_string(),
+            "AnimationFramerate" => self.animation_framerate = value.parse().ok(),
+            "AllowSliderBallTint" => self.allow_slider_ball_tint = parse_bool(value),
+            "ComboBurstRandom" => self.combo_burst_random = parse_bool(value),
+            "CursorCentre" => self.cursor_centre = parse_bool(value),
+            "CursorExpand" => self.cursor_expand = parse_bool(value),
+            "CursorRotate" => self.cursor_rotate = parse_bool(value),
+            "CursorTrailRotate" => self.cursor_trail_rotate = parse_bool(value),
+            "HitCircleOverlayAboveNumber" | "HitCircleOverlayAboveNumer" =>
+            {
+                self.hit_circle_overlay_above_number = parse_bool(value)
+            }
+            "LayeredHitSounds" => self.layered_hit_sounds = parse_bool(value),
+            "SliderBallFlip" => self.slider_ball_flip = parse_bool(value),
+            "SpinnerFadePlayfield" => self.spinner_fade_playfield = parse_bool(value),
+            "SpinnerNoBlink" => self.spinner_no_blink = parse_bool(value),
+            _ =>
+            {}
+        }
+    }
+}
+
+impl SkinColours
+{
+    fn set(&mut self, key: &str, value: &str)
+    {
+        let colour = match parse_colour(value)
+        {
+            Some(colour) => colour,
+            None => return,
+        };
+        match key
+        {
+            // Combo1 to Combo8
+            _ if key.starts_with("Combo") && key[5..].parse::<u8>().is_ok() =>
+            {
+                self.combo_colours.push(colour)
+            }
+            "SliderBorder" => self.slider_border = Some(colour),
+            "SliderTrackOverride" => self.slider_track_override = Some(colour),
+            "SliderBall" => self.slider_ball = Some(colour),
+            "SpinnerBackground" => self.spinner_background = Some(colour),
+            "MenuGlow" => self.menu_glow = Some(colour),
+            "SongSelectActiveText" => self.song_select_active_text = Some(colour),
+            "SongSelectInactiveText" => self.song_select_inactive_text = Some(colour),
+            "StarBreakAdditive" => self.star_break_additive = Some(colour),
+            "InputOverlayText" => self.input_overlay_text = Some(colour),
+            _ =>
+            {}
+        }
+    }
+}
+
+impl SkinFonts
+{
+    fn set(&mut self, key: &str, value: &str)
+    {
+        match key
+        {
+            "HitCirclePrefix" => self.hit_circle_prefix = value.replace('\\', "/"),
+            "HitCircleOverlap" => self.hit_circle_overlap = value.parse().unwrap_or(-2),
+            "ScorePrefix" => self.score_prefix = value.replace('\\', "/"),
+            "ScoreOverlap" => self.score_overlap = value.parse().unwrap_or_default(),
+            "ComboPrefix" => self.combo_prefix = value.replace('\\', "/"),
+            "ComboOverlap" => self.combo_overlap = value.parse().unwrap_or_default(),
+            _ =>
+            {}
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> bool { value == "1" || value.eq_ignore_ascii_case("true") }
+
+/// Parses `r,g,b` colours, an alpha component is ignored.
+fn parse_colour(value: &str) -> Option<Color>
+{
+    let mut components = value.split(',').map(|x| x.trim().parse::<u8>());
+    Some(Color {
+        red:   components.next()?.ok()?,
+        green: components.next()?.ok()?,
+        blue:  components.next()?.ok()?,
+    })
+}
@@ -0,0 +1,216 @@
+pub mod ini;
+
+use std::io::{Read, Seek};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+
+use self::ini::SkinIni;
+use crate::{replay::ParserResult, utils::read_zip_entries};
+
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// Gameplay elements reported as missing when the skin does not provide them.
+///
+/// Number elements are checked separately with the prefixes from [`ini::SkinFonts`].
+const REQUIRED_ELEMENTS: [&str; 11] = [
+    "hitcircle",
+    "hitcircleoverlay",
+    "approachcircle",
+    "sliderb",
+    "sliderfollowcircle",
+    "reversearrow",
+    "cursor",
+    "hit0",
+    "hit50",
+    "hit100",
+    "hit300",
+];
+
+/// Largest recommended @2x size of gameplay elements, SD images may be half as large.
+const MAX_ELEMENT_SIZES: [(&str, u32, u32); 11] = [
+    ("hitcircle", 256, 256),
+    ("hitcircleoverlay", 256, 256),
+    ("sliderstartcircle", 256, 256),
+    ("sliderstartcircleoverlay", 256, 256),
+    ("sliderendcircle", 256, 256),
+    ("sliderendcircleoverlay", 256, 256),
+    ("approachcircle", 256, 256),
+    ("reversearrow", 256, 256),
+    ("sliderb", 256, 256),
+    ("sliderfollowcircle", 512, 512),
+    ("followpoint", 256, 256),
+];
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinImage
+{
+    /// Path of the image inside the archive.
+    pub path:   String,
+    pub size:   u64,
+    /// [`None`] if the image header could not be read.
+    pub width:  Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Element image with its SD and @2x variants.
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct SkinElement
+{
+    /// Lowercase path without the @2x suffix and extension, e.g. `hitcircle` or `hit300-0`.
+    pub name: String,
+    pub sd:   Option<SkinImage>,
+    pub hd:   Option<SkinImage>,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct OversizedElement
+{
+    pub path:       String,
+    pub width:      u32,
+    pub height:     u32,
+    pub max_width:  u32,
+    pub max_height: u32,
+}
+
+#[derive(Default, Serialize, Deserialize, Debug, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ParserSkin
+{
+    /// [`None`] if the skin has no skin.ini.
+    pub ini:                Option<SkinIni>,
+    pub elements:           Vec<SkinElement>,
+    pub missing_elements:   Vec<String>,
+    pub oversized_elements: Vec<OversizedElement>,
+}
+
+impl ParserSkin
+{
+    /// Parses the skin.ini and element images of an .osk archive.
+    pub fn parse<R: Read + Seek>(osk: R) -> ParserResult<Self>
+    {
+        let entries = read_zip_entries(osk)?;
+
+        let ini = entries
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("skin.ini"))
+            .map(|(_, data)| SkinIni::parse(&String::from_utf8_lossy(data)));
+
+        let mut elements: Vec<SkinElement> = Vec::new();
+        for (path, data) in &entries
+        {
+            let (stem, extension) = match path.rsplit_once('.')
+            {
+                Some((stem, extension)) => (stem.to_lowercase(), extension.to_lowercase()),
+                None => continue,
+            };
+            if !IMAGE_EXTENSIONS.contains(&extension.as_str())
+            {
+                continue;
+            }
+
+            let dimensions = imagesize::blob_size(data).ok();
+            let image = SkinImage {
+                path:   path.clone(),
+                size:   data.len() as u64,
+                width:  dimensions.as_ref().map(|x| x.width as u32),
+                height: dimensions.as_ref().map(|x| x.height as u32),
+            };
+            let (name, is_hd) = match stem.strip_suffix("@2x")
+            {
+                Some(name) => (name.replace('\\', "/"), true),
+                None => (stem.replace('\\', "/"), false),
+            };
+
+            let index = match elements.iter().position(|element| element.name == name)
+            {
+                Some(index) => index,
+                None =>
+                {
+                    elements.push(SkinElement {
+                        name,
+                        ..Default::default()
+                    });
+                    elements.len() - 1
+                }
+            };
+            if is_hd
+            {
+                elements[index].hd = Some(image);
+            }
+            else
+            {
+                elements[index].sd = Some(image);
+            }
+        }
+
+        let fonts = ini
+            .as_ref()
+            .map(|ini| &ini.fonts)
+            .cloned()
+            .unwrap_or_default();
+        let numbers = (0..10)
+            .map(|i| format!("{}-{}", fonts.hit_circle_prefix.to_lowercase(), i))
+            .chain((0..10).map(|i| format!("{}-{}", fonts.score_prefix.to_lowercase(), i)));
+        let missing_elements = REQUIRED_ELEMENTS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(numbers)
+            .filter(|name| {
+                // Animated elements only need their first frame
+                ![name.clone(), format!("{}-0", name), format!("{}0", name)]
+                    .iter()
+                    .any(|name| elements.iter().any(|element| &element.name == name))
+            })
+            .unique()
+            .collect_vec();
+
+        let oversized_elements = elements
+            .iter()
+            .flat_map(|element| {
+                let limit = MAX_ELEMENT_SIZES
+                    .iter()
+                    .find(|(name, ..)| *name == animation_base(&element.name));
+                vec![(&element.hd, 1), (&element.sd, 2)]
+                    .into_iter()
+                    .filter_map(move |(image, divisor)| {
+                        let (_, max_width, max_height) = limit?;
+                        let image = image.as_ref()?;
+                        let (width, height) = (image.width?, image.height?);
+                        let (max_width, max_height) = (max_width / divisor, max_height / divisor);
+                        (width > max_width || height > max_height).then(|| OversizedElement {
+                            path: image.path.clone(),
+                            width,
+                            height,
+                            max_width,
+                            max_height,
+                        })
+                    })
+            })
+            .collect_vec();
+
+        Ok(ParserSkin {
+            ini,
+            elements,
+            missing_elements,
+            oversized_elements,
+        })
+    }
+
+    pub fn element(&self, name: &str) -> Option<&SkinElement>
+    {
+        let name = name.to_lowercase();
+        self.elements.iter().find(|element| element.name == name)
+    }
+}
+
+/// Element name without its animation frame index, e.g. `sliderb` for `sliderb0`.
+fn animation_base(name: &str) -> &str
+{
+    name.trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end_matches('-')
+}
@@ -1,3 +1,9 @@
+use std::io::{Read, Seek};
+
+use zip::ZipArchive;
+
+use crate::replay::{ParserError, ParserResult};
+
 pub fn set_panic_hook()
 {
     // When the `console_error_panic_hook` feature is enabled, we can call the
@@ -9,3 +15,48 @@ pub fn set_panic_hook()
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+/// Largest uncompressed file read from a zip archive, videos in .osz archives stay well below it.
+const MAX_ZIP_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+/// Largest uncompressed size of all files read from a zip archive.
+const MAX_ZIP_TOTAL_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Reads every file of a zip archive such as an .osz or .osk into memory.
+///
+/// The sizes in the archive are not trusted, files larger than 256 MiB or archives larger than
+/// 512 MiB uncompressed are rejected.
+pub fn read_zip_entries<R: Read + Seek>(archive: R) -> ParserResult<Vec<(String, Vec<u8>)>>
+{
+    let mut archive = ZipArchive::new(archive)?;
+    let mut entries = Vec::new();
+    let mut total_size = 0;
+    for i in 0..archive.len()
+    {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir()
+        {
+            continue;
+        }
+        let name = file.name().to_string();
+        if file.size() > MAX_ZIP_ENTRY_SIZE
+        {
+            return Err(ParserError::ZipEntryTooLarge(name, MAX_ZIP_ENTRY_SIZE));
+        }
+        let mut data = Vec::new();
+        // One byte more than allowed tells oversized files apart from ones at the limit
+        file.by_ref()
+            .take(MAX_ZIP_ENTRY_SIZE + 1)
+            .read_to_end(&mut data)?;
+        if data.len() as u64 > MAX_ZIP_ENTRY_SIZE
+        {
+            return Err(ParserError::ZipEntryTooLarge(name, MAX_ZIP_ENTRY_SIZE));
+        }
+        total_size += data.len() as u64;
+        if total_size > MAX_ZIP_TOTAL_SIZE
+        {
+            return Err(ParserError::ZipTooLarge(MAX_ZIP_TOTAL_SIZE));
+        }
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
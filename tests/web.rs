@@ -32,6 +32,177 @@ fn test_parse_beatmap() {
     assert!(result.is_ok());
 }
 
+#[wasm_bindgen_test]
+fn test_parse_beatmap_with_mods() {
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let nomod = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    assert_eq!(
+        (nomod.ar, nomod.od, nomod.cs, nomod.hp),
+        (9.0, 8.5, 4.0, 6.0)
+    );
+
+    // DT shortens the 600ms preempt to 400ms and the 28.5ms 300 hit window to 19ms
+    let double_time = corsace_parser::parse_beatmap_with_mods(&mut beatmap, 64).unwrap();
+    assert!((double_time.ar - 31.0 / 3.0).abs() < 1e-3);
+    assert!((double_time.od - 60.5 / 6.0).abs() < 1e-3);
+    assert_eq!((double_time.cs, double_time.hp), (4.0, 6.0));
+    assert!((double_time.bpm.unwrap() - nomod.bpm.unwrap() * 1.5).abs() < 1e-3);
+    assert_eq!(
+        double_time.map_length,
+        (nomod.map_length as f32 / 1.5) as u32
+    );
+
+    // HR caps AR and OD at 10
+    let hard_rock = corsace_parser::parse_beatmap_with_mods(&mut beatmap, 16).unwrap();
+    assert_eq!((hard_rock.ar, hard_rock.od), (10.0, 10.0));
+    assert!((hard_rock.cs - 5.2).abs() < 1e-3);
+    assert!((hard_rock.hp - 8.4).abs() < 1e-3);
+    assert_eq!(hard_rock.bpm, nomod.bpm);
+
+    let easy = corsace_parser::parse_beatmap_with_mods(&mut beatmap, 2).unwrap();
+    assert_eq!((easy.ar, easy.od, easy.cs, easy.hp), (4.5, 4.25, 2.0, 3.0));
+}
+
+/// Minimal beatmap of a mode with a circle every 250ms, spread over the 4 mania columns.
+fn mode_beatmap(mode: u8) -> Vec<u8> {
+    let mut osu = format!(
+        "osu file format v14\n\n[General]\nMode: {}\n\n[Difficulty]\nHPDrainRate:5\n\
+         CircleSize:4\nOverallDifficulty:8\nApproachRate:9\nSliderMultiplier:1.4\n\
+         SliderTickRate:1\n\n[TimingPoints]\n0,500,4,2,0,100,1,0\n\n[HitObjects]\n",
+        mode
+    );
+    for i in 0..64 {
+        osu += &format!(
+            "{},192,{},1,0,0:0:0:0:\n",
+            64 + (i % 4) * 128,
+            1000 + i * 250
+        );
+    }
+    osu.into_bytes()
+}
+
+#[wasm_bindgen_test]
+fn test_beatmap_attributes_modes() {
+    use corsace_parser::beatmap::{ParserBeatmap, ParserModeDifficulty, ParserModePerformance};
+
+    // osu!standard beatmaps are converted to every mode
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let osu = corsace_parser::parse_beatmap_attributes(None, &mut beatmap, None).unwrap();
+    assert!(osu.difficulty.is_some() && osu.performance.is_some());
+    assert!(matches!(
+        osu.mode_difficulty,
+        Some(ParserModeDifficulty::Osu(_))
+    ));
+    let taiko = corsace_parser::parse_beatmap_attributes(None, &mut beatmap, Some(1)).unwrap();
+    assert!(taiko.difficulty.is_none() && taiko.performance.is_none());
+    assert!(matches!(
+        taiko.mode_difficulty,
+        Some(ParserModeDifficulty::Taiko(ref difficulty)) if difficulty.is_convert
+    ));
+    assert!(matches!(
+        taiko.mode_performance,
+        Some(ParserModePerformance::Taiko(_))
+    ));
+    let catch = corsace_parser::parse_beatmap_attributes(None, &mut beatmap, Some(2)).unwrap();
+    assert!(matches!(
+        catch.mode_difficulty,
+        Some(ParserModeDifficulty::Catch(ref difficulty)) if difficulty.is_convert
+    ));
+    let mania = corsace_parser::parse_beatmap_attributes(None, &mut beatmap, Some(3)).unwrap();
+    assert!(matches!(
+        mania.mode_difficulty,
+        Some(ParserModeDifficulty::Mania(ref difficulty)) if difficulty.is_convert
+    ));
+
+    // Beatmaps of other modes use their own mode and can't be converted
+    for mode in 1..=3 {
+        let mut beatmap = mode_beatmap(mode);
+        let own = corsace_parser::parse_beatmap_attributes(None, &mut beatmap, None).unwrap();
+        let converted =
+            corsace_parser::parse_beatmap_attributes(None, &mut beatmap, Some(mode)).unwrap();
+        for attributes in [own, converted] {
+            assert!(attributes.difficulty.is_none());
+            match attributes.mode_difficulty.unwrap() {
+                ParserModeDifficulty::Taiko(difficulty) => {
+                    assert!(mode == 1 && !difficulty.is_convert)
+                }
+                ParserModeDifficulty::Catch(difficulty) => {
+                    assert!(mode == 2 && !difficulty.is_convert)
+                }
+                ParserModeDifficulty::Mania(difficulty) => {
+                    assert!(mode == 3 && !difficulty.is_convert)
+                }
+                ParserModeDifficulty::Osu(_) => panic!("mode {} calculated as osu!standard", mode),
+            }
+        }
+        let other = if mode == 1 { 2 } else { 1 };
+        assert!(
+            ParserBeatmap::parse_beatmap_attributes(None, &mut beatmap.as_slice(), Some(0))
+                .is_err()
+        );
+        assert!(ParserBeatmap::parse_beatmap_attributes(
+            None,
+            &mut beatmap.as_slice(),
+            Some(other)
+        )
+        .is_err());
+    }
+    assert!(ParserBeatmap::parse_beatmap_attributes(None, &mut beatmap.as_ref(), Some(4)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_beatmap_strains_modes() {
+    use corsace_parser::beatmap::ParserModeDifficulty;
+
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let osu = corsace_parser::parse_beatmap_strains(&mut beatmap, None, None, None).unwrap();
+    let objects = osu.mode_difficulty.as_ref().unwrap().len();
+    assert_eq!(osu.difficulty.unwrap().len(), objects);
+    assert!(osu.performance.is_none() && osu.mode_performance.is_none());
+
+    let taiko = corsace_parser::parse_beatmap_strains(&mut beatmap, None, None, Some(1)).unwrap();
+    assert!(taiko.difficulty.is_none());
+    let strains = taiko.mode_difficulty.unwrap();
+    assert!(!strains.is_empty());
+    assert!(strains
+        .iter()
+        .all(|strain| matches!(strain, ParserModeDifficulty::Taiko(_))));
+
+    let mut mania = mode_beatmap(3);
+    let result = corsace_parser::parse_beatmap_strains(&mut mania, None, None, None).unwrap();
+    let strains = result.mode_difficulty.unwrap();
+    assert!(!strains.is_empty() && strains.len() <= 64);
+    assert!(strains
+        .iter()
+        .all(|strain| matches!(strain, ParserModeDifficulty::Mania(_))));
+}
+
+#[wasm_bindgen_test]
+fn test_beatmap_metadata() {
+    use corsace_parser::{beatmap::timing::SampleSet, replay::Mode};
+
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let result = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    assert_eq!(result.format_version, 14);
+    assert_eq!(result.title_unicode, "PINK BLOOD");
+    assert_eq!(result.artist_unicode, "宇多田ヒカル");
+    assert_eq!(result.creator, "VINXIS");
+    assert_eq!(result.source, "不滅のあなたへ");
+    assert_eq!(result.beatmap_id, Some(3054688));
+    assert_eq!(result.beatmapset_id, Some(1490238));
+    assert_eq!(result.audio_filename, "audio.mp3");
+    assert_eq!(result.audio_lead_in, 0);
+    assert_eq!(result.preview_time, 63653);
+    assert_eq!(result.stack_leniency, 0.7);
+    assert_eq!(result.mode, Mode::Osu);
+    assert_eq!(result.countdown, 0);
+    assert_eq!(result.sample_set, SampleSet::Soft);
+    assert!(!result.letterbox_in_breaks);
+    assert!(result.epilepsy_warning);
+    assert!(result.widescreen_storyboard);
+    assert_eq!(result.background.as_deref(), Some("1134108.jpg"));
+}
+
 #[wasm_bindgen_test]
 fn test_parse_replay() {
     let mut replay = include_bytes!("./replay.osr").to_owned();
@@ -39,6 +210,996 @@ fn test_parse_replay() {
     assert!(result.is_ok());
 }
 
+#[wasm_bindgen_test]
+fn test_replay_roundtrip() {
+    let replay = include_bytes!("./replay.osr").to_owned();
+    let parsed = corsace_parser::replay::Replay::parse(&mut replay.as_ref(), true).unwrap();
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, replay);
+}
+
+#[wasm_bindgen_test]
+fn test_replay_judgements() {
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let stored = corsace_parser::parse_replay(&mut replay).unwrap();
+    let result = corsace_parser::parse_replay_judgements(&mut replay, &mut beatmap).unwrap();
+    assert_eq!(result.objects.len(), 566);
+
+    // osu! judged two circles pressed 27ms late as 100s, which fall in the 300 window here
+    assert_eq!(result.judgements.count_300, Some(540));
+    assert_eq!(result.judgements.count_100, Some(25));
+    assert_eq!(stored.judgements.count_300, Some(538));
+    assert_eq!(stored.judgements.count_100, Some(27));
+    assert_eq!(result.judgements.count_50, stored.judgements.count_50);
+    assert_eq!(result.judgements.miss, stored.judgements.miss);
+    assert_eq!(result.max_combo, stored.max_combo as u32);
+}
+
+#[wasm_bindgen_test]
+fn test_replay_hit_errors() {
+    use corsace_parser::replay::{
+        judgement::{ObjectJudgement, ReplayJudgements},
+        Mods,
+    };
+
+    let objects = [
+        Some(-12.0),
+        Some(-3.0),
+        Some(6.0),
+        Some(9.0),
+        Some(15.0),
+        None,
+    ]
+    .iter()
+    .map(|hit_offset| ObjectJudgement {
+        hit_offset: *hit_offset,
+        ..Default::default()
+    })
+    .collect();
+    let judgements = ReplayJudgements {
+        objects,
+        ..Default::default()
+    };
+
+    // Deviations from the mean of 3ms are -15, -6, 3, 6 and 12, their variance is 450 / 5
+    let result = judgements.hit_errors(Mods::empty());
+    assert_eq!(result.hit_offsets, vec![-12.0, -3.0, 6.0, 9.0, 15.0]);
+    assert!((result.mean_error - 3.0).abs() < 1e-9);
+    assert!((result.unstable_rate - 90.0f64.sqrt() * 10.0).abs() < 1e-9);
+    assert_eq!((result.early_count, result.late_count), (2, 3));
+    assert!((result.early_mean + 7.5).abs() < 1e-9);
+    assert!((result.late_mean - 10.0).abs() < 1e-9);
+    assert_eq!(result.histogram.len(), 7);
+    assert_eq!(result.histogram[0].offset, -15.0);
+    assert_eq!(result.histogram.iter().map(|bin| bin.count).sum::<u32>(), 5);
+
+    // DT plays 1.5 times faster, so every offset is 1.5 times shorter in real time
+    let result = judgements.hit_errors(Mods::DoubleTime);
+    assert_eq!(result.hit_offsets, vec![-12.0, -3.0, 6.0, 9.0, 15.0]);
+    assert!((result.mean_error - 2.0).abs() < 1e-9);
+    assert!((result.unstable_rate - 90.0f64.sqrt() * 10.0 / 1.5).abs() < 1e-9);
+    assert!((result.early_mean + 5.0).abs() < 1e-9);
+}
+
+#[wasm_bindgen_test]
+fn test_replay_score() {
+    use corsace_parser::{
+        beatmap::{objects::HitObject, ParserBeatmap},
+        replay::{Judgements, Mods},
+        ParserScore,
+    };
+
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let stored = corsace_parser::parse_replay(&mut replay).unwrap();
+    let result = corsace_parser::parse_replay_score(&mut replay, &mut beatmap).unwrap();
+    assert!(!result.estimated);
+    assert!((result.score_v1 as f64 / stored.score as f64 - 1.0).abs() < 0.01);
+    assert!(result.score_v2 > 0 && result.score_v2 < 1_000_000);
+
+    // The miss breaks the combo, so the combo weighted hits stay far below a full combo
+    assert!(result.combo_portion > 0.0 && result.combo_portion < 700_000.0 * 0.5);
+
+    let score = |count_300, count_100, miss, combo| ParserScore {
+        mods: Some(0),
+        combo: Some(combo as usize),
+        judgements: Some(Judgements {
+            count_300: Some(count_300),
+            count_100: Some(count_100),
+            count_50: Some(0),
+            miss: Some(miss),
+            ..Default::default()
+        }),
+        passed_objects: None,
+        clock_rate: None,
+        accuracy: None,
+    };
+    let max_combo = corsace_parser::parse_beatmap(&mut beatmap)
+        .unwrap()
+        .max_combo;
+    let full_combo =
+        corsace_parser::calculate_score(score(566, 0, 0, max_combo), &mut beatmap).unwrap();
+    assert!(full_combo.estimated);
+    assert!(full_combo.combo_portion > 700_000.0 * 0.99);
+    assert_eq!(full_combo.accuracy_portion, 300_000.0);
+
+    // Counts lose where the combo broke, the streak estimate lands near the replay
+    let estimated =
+        corsace_parser::calculate_score(score(538, 27, 1, stored.max_combo as u32), &mut beatmap)
+            .unwrap();
+    assert!(estimated.estimated);
+    assert!(estimated.combo_portion < full_combo.combo_portion);
+    assert!((estimated.combo_portion / result.combo_portion - 1.0).abs() < 0.25);
+
+    // 1.6s of drain count as 1s, making the difficulty multiplier 3 instead of 2
+    let beatmap = ParserBeatmap {
+        hp: 5.0,
+        hit_objects: Some(
+            (0..3)
+                .map(|i| HitObject {
+                    start_time: i as f64 * 800.0,
+                    ..Default::default()
+                })
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    let judgements = Judgements {
+        count_300: Some(3),
+        ..Default::default()
+    };
+    let result = judgements.score(3, &beatmap, Mods::empty()).unwrap();
+    assert_eq!(result.score_v1, 300 + 300 + 300 + 300 * 3 / 25);
+}
+
+#[wasm_bindgen_test]
+fn test_replay_grade() {
+    use corsace_parser::replay::{Grade, Judgements};
+
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let result = corsace_parser::parse_replay_grade(&mut replay).unwrap();
+    assert!((result.accuracy - (538.0 * 300.0 + 27.0 * 100.0) / (566.0 * 300.0)).abs() < 1e-9);
+    assert_eq!(result.grade, Grade::A);
+
+    let mania = Judgements {
+        count_geki: Some(900),
+        count_300: Some(80),
+        count_katu: Some(15),
+        count_100: Some(5),
+        count_50: Some(0),
+        miss: Some(0),
+    };
+    let result = corsace_parser::calculate_grade(mania, 3, 8).unwrap();
+    assert!((result.accuracy - (980.0 * 300.0 + 15.0 * 200.0 + 500.0) / 300_000.0).abs() < 1e-9);
+    assert_eq!(result.grade, Grade::SH);
+
+    let perfect = Judgements {
+        count_300: Some(100),
+        ..Default::default()
+    };
+    assert_eq!(
+        corsace_parser::calculate_grade(perfect, 1, 1024)
+            .unwrap()
+            .grade,
+        Grade::SSH
+    );
+    assert_eq!(
+        corsace_parser::calculate_grade(perfect, 2, 0)
+            .unwrap()
+            .grade,
+        Grade::SS
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_replay_life_graph() {
+    use corsace_parser::replay::{judgement::HitResult, Mods, Replay};
+
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let result = corsace_parser::parse_replay_life_graph(&mut replay, &mut beatmap).unwrap();
+    assert!(result.passed);
+    assert!(result.fail_time.is_none());
+    assert!(result.drain_rate > 0.0);
+    assert!(result
+        .points
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time));
+    assert!(result
+        .points
+        .iter()
+        .all(|point| point.life_value > 0.5 && point.life_value <= 1.0));
+
+    // Missing everything after the first 200 objects drains health to 0
+    let (parsed, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref()).unwrap();
+    // Follows the life graph stored by osu! stable
+    assert!(parsed.life_graph.iter().all(|stored| {
+        let closest = result
+            .points
+            .iter()
+            .min_by_key(|point| (point.time - stored.time).abs())
+            .unwrap();
+        (closest.life_value - stored.life_value).abs() < 0.2
+    }));
+    let mut judgements = parsed.simulate_judgements(&beatmap).unwrap();
+    for object in judgements.objects.iter_mut().skip(200) {
+        object.result = HitResult::Miss;
+        object.hit_offset = None;
+        if let Some(slider) = object.slider.as_mut() {
+            slider.head_hit = false;
+            slider.ticks_hit = 0;
+            slider.repeats_hit = 0;
+            slider.end_hit = false;
+        }
+    }
+    let failed = judgements.life_graph(&beatmap, Mods::empty()).unwrap();
+    assert!(!failed.passed);
+    let fail_time = failed.fail_time.unwrap();
+    assert_eq!(failed.points.last().unwrap().life_value, 0.0);
+    assert_eq!(failed.points.last().unwrap().time, fail_time.round() as i32);
+
+    let no_fail = judgements.life_graph(&beatmap, Mods::NoFail).unwrap();
+    assert!(no_fail.passed);
+    assert_eq!(no_fail.fail_time, Some(fail_time));
+    assert!(no_fail.points.len() > failed.points.len());
+}
+
+#[wasm_bindgen_test]
+fn test_replay_analysis() {
+    use corsace_parser::replay::Replay;
+
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let analysis = corsace_parser::parse_replay_analysis(&mut replay, &mut beatmap).unwrap();
+    assert_eq!(analysis.suspicion, 0.0);
+    assert!(analysis.hit_error_variance.value > 0.0);
+    assert!(analysis.cursor_velocity.frames.is_empty());
+
+    // Teleporting the cursor for one frame moves it impossibly fast twice
+    let (mut parsed, beatmap) =
+        Replay::parse_with_beatmap(&mut replay.as_ref(), &mut beatmap.as_ref()).unwrap();
+    let frames = &mut parsed.replay_frame_data.as_mut().unwrap().frames;
+    frames[1000].cursor_pos.x += 1000.0;
+    let analysis = parsed.analyze(&beatmap).unwrap();
+    assert_eq!(analysis.cursor_velocity.value, 2.0);
+    assert_eq!(analysis.cursor_velocity.frames.len(), 1);
+    assert_eq!(analysis.cursor_velocity.frames[0].start_frame, 999);
+    assert_eq!(analysis.cursor_velocity.frames[0].end_frame, 1001);
+}
+
+#[wasm_bindgen_test]
+fn test_replay_analysis_clean_presses() {
+    use corsace_parser::{
+        beatmap::{objects::Pos2, ParserBeatmap},
+        replay::{
+            judgement::{HitResult, ObjectJudgement, ReplayJudgements},
+            Buttons, Mods, ReplayFrame, ReplayFrameData,
+        },
+    };
+
+    // A clean play with a press on every object and no stray presses
+    let frames = ReplayFrameData {
+        frames: (0..100)
+            .map(|i| ReplayFrame {
+                timestamp_ms: i * 16,
+                time_since_ms: 16,
+                cursor_pos: Pos2 {
+                    x: 100.0 + i as f32,
+                    y: 200.0,
+                },
+                buttons: if i % 10 == 0 && i > 0 {
+                    Buttons::M1
+                } else {
+                    Buttons::empty()
+                },
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let offsets = [-20.0, -8.0, 5.0, 15.0, 25.0, -15.0, 10.0, -3.0, 18.0];
+    let judgements = ReplayJudgements {
+        objects: offsets
+            .iter()
+            .enumerate()
+            .map(|(i, offset)| ObjectJudgement {
+                object_index: i,
+                time: ((i + 1) * 160) as f64 - offset,
+                result: HitResult::Hit300,
+                hit_offset: Some(*offset),
+                frame_index: Some((i + 1) * 10),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let analysis = frames.analyze(&judgements, &ParserBeatmap::default(), Mods::empty());
+    assert_eq!(analysis.presses_on_objects.value, 1.0);
+    assert_eq!(analysis.presses_on_objects.score, 1.0);
+    assert_eq!(analysis.suspicion, 0.0);
+}
+
+#[wasm_bindgen_test]
+fn test_replay_stats() {
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let stats = corsace_parser::parse_replay_stats(&mut replay).unwrap();
+    assert_eq!(
+        stats.total_presses,
+        stats.k1.presses + stats.k2.presses + stats.m1.presses + stats.m2.presses
+    );
+    assert_eq!(stats.k1.press_durations.len() as u32, stats.k1.presses);
+    assert_eq!(stats.frame_times.median, 17.0);
+    assert_eq!(stats.skips.len(), 1);
+    assert!(stats.pauses.is_empty());
+    assert!(stats.cursor_distance > 0.0 && stats.average_cursor_speed > 0.0);
+    assert!(stats.tapping_bpm.iter().all(|point| point.bpm > 0.0));
+}
+
+#[wasm_bindgen_test]
+fn test_replay_timewarp() {
+    use corsace_parser::replay::{Mods, Replay};
+
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let report = corsace_parser::parse_replay_timewarp(&mut replay).unwrap();
+    assert_eq!(report.clock_rate, 1.0);
+    assert_eq!(report.suspicion, 0.0);
+    assert!(report.evidence.is_empty());
+
+    // DT and HT replays record frames about every 11.1ms and 22.2ms
+    for (mods, clock_rate, expected_median) in
+        [(Mods::DoubleTime, 1.5, 11.0), (Mods::HalfTime, 0.75, 22.0)]
+    {
+        let mut parsed = Replay::parse(&mut replay.as_ref(), true).unwrap();
+        parsed.decode_frames().unwrap();
+        parsed.mods = mods;
+        for frame in &mut parsed.replay_frame_data.as_mut().unwrap().frames {
+            if frame.time_since_ms > 0 {
+                frame.time_since_ms = (frame.time_since_ms as f64 / clock_rate).round() as i32;
+            }
+        }
+        let report = parsed.timewarp().unwrap();
+        assert!((report.frame_times.median / clock_rate - expected_median).abs() <= 1.0);
+        assert!((report.frame_times.median - 1000.0 / 60.0).abs() < 1.5);
+        assert!((report.apparent_clock_rate - clock_rate).abs() < 0.1);
+        assert_eq!(report.suspicion, 0.0);
+    }
+
+    // The same frames under DT look like the game was slowed down to 2/3 speed
+    let mut parsed = Replay::parse(&mut replay.as_ref(), true).unwrap();
+    parsed.decode_frames().unwrap();
+    let report = parsed.replay_frame_data.unwrap().timewarp(Mods::DoubleTime);
+    assert!((report.apparent_clock_rate - 1.0).abs() < 0.1);
+    assert_eq!(report.suspicion, 1.0);
+    assert!(!report.evidence.is_empty());
+}
+
+#[wasm_bindgen_test]
+fn test_replay_similarity() {
+    use corsace_parser::replay::{Mods, Replay};
+
+    let mut replay = include_bytes!("./replay.osr").to_owned();
+    let mut other = include_bytes!("./replay.osr").to_owned();
+    let similarity = corsace_parser::compare_replays(&mut replay, &mut other, true).unwrap();
+    assert_eq!(similarity.mean_distance, 0.0);
+    assert!((similarity.correlation - 1.0).abs() < 1e-9);
+    assert_eq!(similarity.matched_presses, 1.0);
+    assert_eq!(similarity.press_time_difference, 0.0);
+
+    // A copy played with HR is flipped back before comparing
+    let mut parsed = Replay::parse(&mut replay.as_ref(), true).unwrap();
+    parsed.decode_frames().unwrap();
+    let mut flipped = Replay::parse(&mut replay.as_ref(), true).unwrap();
+    flipped.decode_frames().unwrap();
+    flipped.mods |= Mods::HardRock;
+    // The frames osu! places at (256, -500) before gameplay are not flipped
+    let frames = &mut flipped.replay_frame_data.as_mut().unwrap().frames;
+    for frame in frames
+        .iter_mut()
+        .filter(|frame| frame.cursor_pos.y != -500.0)
+    {
+        frame.cursor_pos = frame.cursor_pos.flip_vertically();
+    }
+    let similarity = parsed.similarity(&flipped, true).unwrap();
+    assert!(similarity.flipped);
+    assert!(similarity.mean_distance < 1e-3);
+    let similarity = parsed.similarity(&flipped, false).unwrap();
+    assert!(similarity.mean_distance > 20.0);
+    assert!(similarity.correlation < 1.0);
+}
+
+#[wasm_bindgen_test]
+fn test_parse_beatmap_sliders() {
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let parsed = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    let result = corsace_parser::parse_beatmap_sliders(&mut beatmap, Some(8.0)).unwrap();
+    assert_eq!(result.sliders.len(), parsed.sliders as usize);
+    for slider in result.sliders {
+        assert!(slider.end_time > slider.start_time);
+        assert_eq!(slider.repeats.len(), slider.span_count - 1);
+        let expected_end = if slider.span_count % 2 == 1 {
+            *slider.path.last().unwrap()
+        } else {
+            slider.path[0]
+        };
+        assert!(expected_end.distance(slider.end_position) < 1.0);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_beatmap_stacking() {
+    use corsace_parser::beatmap::{
+        objects::{HitObject, HitObjectKind, Pos2},
+        ParserBeatmap,
+    };
+    use corsace_parser::replay::Mods;
+
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let parsed = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    let result = corsace_parser::parse_beatmap_stacking(&mut beatmap, 0).unwrap();
+    assert_eq!(
+        result.objects.len(),
+        parsed.hit_objects.as_ref().unwrap().len()
+    );
+
+    let stream = |format_version| ParserBeatmap {
+        format_version,
+        stack_leniency: 0.7,
+        ar: 9.0,
+        cs: 4.0,
+        hit_objects: Some(
+            (0..3)
+                .map(|i| HitObject {
+                    pos: Pos2 { x: 256.0, y: 100.0 },
+                    start_time: 1000.0 + i as f64 * 100.0,
+                    ..Default::default()
+                })
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    for format_version in vec![5, 14] {
+        let result = stream(format_version).stacking(Mods::empty());
+        let heights = result
+            .objects
+            .iter()
+            .map(|object| object.stack_height)
+            .collect::<Vec<_>>();
+        assert_eq!(heights, [2, 1, 0]);
+        assert!(result.objects[0].stacked_position.x < result.objects[2].stacked_position.x);
+    }
+
+    // Old stacking continues from the start time of a long object, not its end time
+    let mut spinner = stream(5);
+    let objects = spinner.hit_objects.as_mut().unwrap();
+    objects[1].kind = HitObjectKind::Spinner { end_time: 5000.0 };
+    objects[2].start_time = 5100.0;
+    let heights = spinner
+        .stacking(Mods::empty())
+        .objects
+        .iter()
+        .map(|object| object.stack_height)
+        .collect::<Vec<_>>();
+    assert_eq!(heights, [1, 1, 0]);
+
+    let flipped = stream(14).stacking(Mods::HardRock);
+    assert_eq!(
+        flipped.objects[2].stacked_position,
+        Pos2 { x: 256.0, y: 284.0 }
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_beatmap_transformed() {
+    use corsace_parser::beatmap::{
+        objects::{HitObject, HitObjectKind, PathControlPoint, Pos2},
+        ParserBeatmap, ParserBreak,
+    };
+    use corsace_parser::replay::Mods;
+
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let parsed = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    let transformed = corsace_parser::parse_beatmap_transformed(&mut beatmap, 64 | 16).unwrap();
+    let objects = parsed.hit_objects.unwrap();
+    let transformed_objects = transformed.hit_objects.unwrap();
+    assert_eq!(transformed_objects.len(), objects.len());
+    assert_eq!(
+        transformed_objects[0].start_time,
+        objects[0].start_time / 1.5
+    );
+    assert_eq!(transformed_objects[0].pos.y, 384.0 - objects[0].pos.y);
+
+    let slider = || ParserBeatmap {
+        hit_objects: Some(vec![HitObject {
+            pos: Pos2 { x: 100.0, y: 100.0 },
+            start_time: 3000.0,
+            kind: HitObjectKind::Slider {
+                pixel_len: None,
+                repeats: 0,
+                control_points: vec![
+                    PathControlPoint::default(),
+                    PathControlPoint {
+                        pos: Pos2 { x: 50.0, y: 20.0 },
+                        kind: None,
+                    },
+                ],
+                edge_sounds: Vec::new(),
+            },
+            ..Default::default()
+        }]),
+        breaks: Some(vec![ParserBreak {
+            start_time: 6000,
+            end_time: 9000,
+        }]),
+        ..Default::default()
+    };
+    let transformed = slider().transformed(Mods::HardRock | Mods::Mirror | Mods::DoubleTime);
+    let object = &transformed.hit_objects.unwrap()[0];
+    assert_eq!(object.pos, Pos2 { x: 412.0, y: 284.0 });
+    assert_eq!(object.start_time, 2000.0);
+    match &object.kind {
+        HitObjectKind::Slider { control_points, .. } => {
+            assert_eq!(control_points[1].pos, Pos2 { x: -50.0, y: -20.0 })
+        }
+        _ => unreachable!(),
+    }
+    let breaks = transformed.breaks.unwrap();
+    assert_eq!((breaks[0].start_time, breaks[0].end_time), (4000, 6000));
+
+    let half_time = slider().transformed(Mods::HalfTime);
+    assert_eq!(half_time.hit_objects.unwrap()[0].start_time, 4000.0);
+}
+
+#[wasm_bindgen_test]
+fn test_beatmap_roundtrip() {
+    use corsace_parser::beatmap::objects::Pos2;
+
+    // Event offsets and slider colours which the fixture does not set
+    let mut beatmap = include_str!("./beatmap.osu")
+        .replace(
+            "0,0,\"1134108.jpg\",0,0",
+            "0,0,\"1134108.jpg\",10,-20\r\nVideo,1500,\"video.mp4\",5,5",
+        )
+        .replace(
+            "Combo6 : 234,194,170",
+            "Combo6 : 234,194,170\r\nSliderTrackOverride : 1,2,3\r\nSliderBorder : 4,5,6",
+        )
+        .into_bytes();
+    let parsed = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    assert_eq!(parsed.background_offset, Pos2 { x: 10.0, y: -20.0 });
+    assert_eq!(parsed.video.as_deref(), Some("video.mp4"));
+    assert_eq!(parsed.video_start_time, 1500);
+    assert_eq!(parsed.slider_border.as_ref().unwrap().blue, 6);
+    assert_eq!(parsed.editor.len(), 4);
+    let mut written = corsace_parser::serialize_beatmap(parsed).unwrap();
+    let reparsed = corsace_parser::parse_beatmap(&mut written).unwrap();
+
+    let parsed = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    let mut expected = serde_json::to_value(&parsed).unwrap();
+    let mut actual = serde_json::to_value(&reparsed).unwrap();
+    for value in vec![&mut expected, &mut actual] {
+        value["hash"] = serde_json::Value::Null;
+    }
+    assert_eq!(actual, expected);
+}
+
+#[wasm_bindgen_test]
+fn test_rate_changed_beatmap() {
+    let mut beatmap = include_bytes!("./beatmap.osu").to_owned();
+    let parsed = corsace_parser::parse_beatmap(&mut beatmap).unwrap();
+    let generated = corsace_parser::generate_rate_changed_beatmap(&mut beatmap, 1.2).unwrap();
+    assert_eq!(
+        generated.hash,
+        format!("{:x}", md5::compute(&generated.osu))
+    );
+
+    let mut osu = generated.osu.into_bytes();
+    let rate_changed = corsace_parser::parse_beatmap(&mut osu).unwrap();
+    assert_eq!(rate_changed.hash, generated.hash);
+    assert_eq!(
+        rate_changed.diff_name,
+        format!("{} (1.2x)", parsed.diff_name)
+    );
+    assert!((rate_changed.bpm.unwrap() - parsed.bpm.unwrap() * 1.2).abs() < 0.01);
+    assert!(rate_changed.ar > parsed.ar && rate_changed.od > parsed.od);
+    assert!(generated.uncapped_ar.is_none() && generated.uncapped_od.is_none());
+    // Rate changed beatmaps are unsubmitted, BeatmapID:0 is read back as no ID
+    assert!(String::from_utf8_lossy(&osu).contains("BeatmapID:0"));
+    assert_eq!(rate_changed.beatmap_id, None);
+    let objects = parsed.hit_objects.unwrap();
+    let rate_changed_objects = rate_changed.hit_objects.unwrap();
+    assert_eq!(
+        rate_changed_objects[0].start_time,
+        (objects[0].start_time / 1.2).round()
+    );
+    // Offsets are rounded like hit object times
+    let timing_points = parsed.timing_points.unwrap();
+    let rate_changed_points = rate_changed.timing_points.unwrap();
+    assert_eq!(
+        rate_changed_points[0].time,
+        (timing_points[0].time / 1.2).round()
+    );
+    assert!(rate_changed_points
+        .iter()
+        .all(|point| point.time.fract() == 0.0));
+
+    // Storyboard and video times follow the rate
+    let mut storyboarded = include_str!("./beatmap.osu")
+        .replace(
+            "0,0,\"1134108.jpg\",0,0",
+            "0,0,\"1134108.jpg\",0,0\r\nVideo,1200,\"video.mp4\"\r\nSprite,Foreground,Centre,\"sb.png\",320,240\r\n _F,0,1200,2400,0,1\r\n _L,600,2\r\n  _M,0,0,1200,320,240\r\nSample,1200,0,\"clap.wav\",100",
+        )
+        .into_bytes();
+    let generated = corsace_parser::generate_rate_changed_beatmap(&mut storyboarded, 1.2).unwrap();
+    let mut osu = generated.osu.into_bytes();
+    let rate_changed = corsace_parser::parse_beatmap(&mut osu).unwrap();
+    assert_eq!(rate_changed.video_start_time, 1000);
+    assert_eq!(
+        rate_changed.storyboard,
+        [
+            "Sprite,Foreground,Centre,\"sb.png\",320,240",
+            " _F,0,1000,2000,0,1",
+            " _L,500,2",
+            "  _M,0,0,1000,320,240",
+            "Sample,1000,0,\"clap.wav\",100",
+        ]
+    );
+
+    // 1.5x would need AR 10.33 and OD 10.08
+    let capped = corsace_parser::generate_rate_changed_beatmap(&mut beatmap, 1.5).unwrap();
+    assert!((capped.uncapped_ar.unwrap() - 31.0 / 3.0).abs() < 1e-3);
+    assert!((capped.uncapped_od.unwrap() - 60.5 / 6.0).abs() < 1e-3);
+    let mut osu = capped.osu.into_bytes();
+    let rate_changed = corsace_parser::parse_beatmap(&mut osu).unwrap();
+    assert_eq!((rate_changed.ar, rate_changed.od), (10.0, 10.0));
+
+    // 0.25x would need AR -5 and OD -5.75
+    let clamped = corsace_parser::generate_rate_changed_beatmap(&mut beatmap, 0.25).unwrap();
+    assert!((clamped.uncapped_ar.unwrap() + 5.0).abs() < 1e-3);
+    assert!((clamped.uncapped_od.unwrap() + 5.75).abs() < 1e-3);
+    let mut osu = clamped.osu.into_bytes();
+    let rate_changed = corsace_parser::parse_beatmap(&mut osu).unwrap();
+    assert_eq!((rate_changed.ar, rate_changed.od), (0.0, 0.0));
+
+    assert!(corsace_parser::generate_rate_changed_beatmap(&mut beatmap, 0.0).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_timing_points() {
+    use corsace_parser::beatmap::{sections::OsuSections, ParserBeatmap};
+
+    let sections = OsuSections::parse(include_str!("./beatmap.osu"));
+    let mut beatmap = ParserBeatmap::default().extend_from_sections(&sections);
+    beatmap.map_length = 180000;
+    let timing_points = beatmap.timing_points.as_ref().unwrap();
+    assert_eq!(timing_points.len(), 25);
+    assert_eq!(
+        timing_points
+            .iter()
+            .filter(|point| point.uninherited)
+            .count(),
+        1
+    );
+    assert_eq!(
+        beatmap.beat_length_at(200000.0),
+        timing_points[0].beat_length
+    );
+
+    let timing_ranges = beatmap.timing_ranges();
+    let kiai = timing_ranges.kiai_intervals;
+    assert_eq!(kiai.len(), 1);
+    assert_eq!((kiai[0].start_time, kiai[0].end_time), (135316.0, 173716.0));
+
+    let ranges = timing_ranges.slider_velocity_ranges;
+    assert_eq!(ranges[0].slider_velocity, 1.0);
+    assert!((ranges[1].slider_velocity - 1.5).abs() < 1e-9);
+    assert_eq!(
+        beatmap.slider_velocity_at(ranges[1].start_time),
+        ranges[1].slider_velocity
+    );
+    assert_eq!(ranges.last().unwrap().end_time, 180000.0);
+    for window in ranges.windows(2) {
+        assert_eq!(window[0].end_time, window[1].start_time);
+        assert_ne!(window[0].slider_velocity, window[1].slider_velocity);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_lazer_score_info() {
+    use corsace_parser::replay::{lazer::LazerScoreInfo, Replay};
+
+    let replay = include_bytes!("./replay.osr").to_owned();
+    let mut parsed = Replay::parse(&mut replay.as_ref(), true).unwrap();
+    parsed.version = 30000016;
+    parsed.lazer_score_info = Some(LazerScoreInfo {
+        statistics: [("great".to_string(), 538), ("ok".to_string(), 27)].into(),
+        rank: Some("A".to_string()),
+        extra: serde_json::json!({ "pauses": [1200], "total_score": 912345 })
+            .as_object()
+            .unwrap()
+            .clone(),
+        ..Default::default()
+    });
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+
+    let reparsed = Replay::parse(&mut written.as_slice(), false).unwrap();
+    let score_info = reparsed.lazer_score_info.unwrap();
+    assert_eq!(score_info.statistics["great"], 538);
+    assert_eq!(score_info.rank.as_deref(), Some("A"));
+    assert_eq!(score_info.extra["pauses"], serde_json::json!([1200]));
+    assert_eq!(score_info.extra["total_score"], 912345);
+    assert_eq!(reparsed.score_id, parsed.score_id);
+}
+
+#[wasm_bindgen_test]
+fn test_osu_database() {
+    use byteorder::{LittleEndian, WriteBytesExt};
+    use corsace_parser::{
+        beatmap::ParserBeatmap,
+        database::osu::{BeatmapAvailability, OsuDatabase},
+        replay::{write::ULEB128Encode, Grade},
+    };
+
+    let mut database = Vec::new();
+    database.write_u32::<LittleEndian>(20250108).unwrap();
+    database.write_u32::<LittleEndian>(1).unwrap();
+    database.push(1);
+    database.write_u64::<LittleEndian>(0).unwrap();
+    database.write_uleb128_string("player").unwrap();
+    database.write_u32::<LittleEndian>(1).unwrap();
+    // Artist, title, creator, difficulty, audio, hash and file name
+    for value in ["a", "a", "t", "t", "c", "Hard", "audio.mp3", "abc", "f.osu"] {
+        database.write_uleb128_string(value).unwrap();
+    }
+    database.push(4);
+    for _ in 0..3 {
+        database.write_u16::<LittleEndian>(5).unwrap();
+    }
+    database.write_u64::<LittleEndian>(0).unwrap();
+    for _ in 0..4 {
+        database.write_f32::<LittleEndian>(9.0).unwrap();
+    }
+    database.write_f64::<LittleEndian>(1.4).unwrap();
+    // One cached DT star rating for osu!standard, none for the other modes
+    database.write_u32::<LittleEndian>(1).unwrap();
+    database.push(0x08);
+    database.write_u32::<LittleEndian>(64).unwrap();
+    database.push(0x0c);
+    database.write_f32::<LittleEndian>(6.5).unwrap();
+    for _ in 0..3 {
+        database.write_u32::<LittleEndian>(0).unwrap();
+    }
+    database.write_u32::<LittleEndian>(100).unwrap();
+    database.write_u32::<LittleEndian>(1000).unwrap();
+    database.write_i32::<LittleEndian>(-1).unwrap();
+    database.write_u32::<LittleEndian>(1).unwrap();
+    database.write_f64::<LittleEndian>(300.0).unwrap();
+    database.write_f64::<LittleEndian>(10.0).unwrap();
+    database.push(1);
+    database.write_i32::<LittleEndian>(42).unwrap();
+    database.write_i32::<LittleEndian>(7).unwrap();
+    database.write_i32::<LittleEndian>(0).unwrap();
+    // An A in osu!standard, not played in taiko and catch and an unknown mania grade
+    database.extend([4, 9, 9, 12]);
+    database.write_i16::<LittleEndian>(0).unwrap();
+    database.write_f32::<LittleEndian>(0.7).unwrap();
+    database.push(0);
+    database.write_uleb128_string("").unwrap();
+    database.write_uleb128_string("tag").unwrap();
+    database.write_i16::<LittleEndian>(0).unwrap();
+    database.write_uleb128_string("").unwrap();
+    database.push(0);
+    database.write_u64::<LittleEndian>(0).unwrap();
+    database.push(0);
+    database.write_uleb128_string("folder").unwrap();
+    database.write_u64::<LittleEndian>(0).unwrap();
+    database.extend([0, 0, 0, 0, 0]);
+    database.write_u32::<LittleEndian>(0).unwrap();
+    database.push(0);
+    database.write_u32::<LittleEndian>(1).unwrap();
+
+    let parsed = OsuDatabase::parse(&mut database.as_slice()).unwrap();
+    assert_eq!(parsed.username, "player");
+    assert_eq!(parsed.beatmaps.len(), 1);
+    let entry = &parsed.beatmaps[0];
+    assert_eq!(entry.folder_name, "folder");
+    assert_eq!(entry.star_ratings.osu[0].stars, 6.5);
+    assert_eq!(entry.timing_points.len(), 1);
+    assert_eq!((entry.beatmap_id, entry.beatmapset_id), (42, 7));
+    assert_eq!(entry.grades.osu, Some(Grade::A));
+    assert!(entry.grades.taiko.is_none() && entry.grades.mania.is_none());
+    assert_eq!(parsed.permissions, 1);
+
+    let mut beatmap = ParserBeatmap {
+        hash: "abc".to_string(),
+        beatmap_id: Some(42),
+        ..Default::default()
+    };
+    assert_eq!(
+        parsed.check_beatmap(&beatmap).availability,
+        BeatmapAvailability::UpToDate
+    );
+    beatmap.hash = "def".to_string();
+    let check = parsed.check_beatmap(&beatmap);
+    assert_eq!(check.availability, BeatmapAvailability::Outdated);
+    assert_eq!(check.local_hash.as_deref(), Some("abc"));
+    beatmap.beatmap_id = Some(43);
+    assert_eq!(
+        parsed.check_beatmap(&beatmap).availability,
+        BeatmapAvailability::Missing
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_collection_database() {
+    use corsace_parser::database::collection::CollectionDatabase;
+
+    let hashes = vec!["a".repeat(32), "b".repeat(32)];
+    let existing =
+        corsace_parser::generate_collection_database("pool", hashes[..1].to_vec(), None).unwrap();
+    let merged =
+        corsace_parser::generate_collection_database("pool", hashes.clone(), Some(existing))
+            .unwrap();
+
+    let parsed = CollectionDatabase::parse(&mut merged.as_slice()).unwrap();
+    assert_eq!(parsed.collections.len(), 1);
+    assert_eq!(parsed.collections[0].beatmap_hashes, hashes);
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, merged);
+}
+
+#[wasm_bindgen_test]
+fn test_scores_database() {
+    use byteorder::{LittleEndian, WriteBytesExt};
+    use corsace_parser::{database::scores::ScoresDatabase, replay::Replay};
+
+    // scores.db entries are replay headers with a replay data length of -1
+    let replay = include_bytes!("./replay.osr").to_owned();
+    let mut header = Replay::parse(&mut replay.as_ref(), false).unwrap();
+    header.replay_data = None;
+    let mut entry = Vec::new();
+    header.write(&mut entry).unwrap();
+    let length_offset = entry.len() - 12;
+    entry[length_offset..length_offset + 4].copy_from_slice(&(-1i32).to_le_bytes());
+
+    let mut database = Vec::new();
+    database.write_u32::<LittleEndian>(20240820).unwrap();
+    database.write_u32::<LittleEndian>(1).unwrap();
+    database.push(0x0B);
+    database.push(header.beatmap_hash.len() as u8);
+    database.extend_from_slice(header.beatmap_hash.as_bytes());
+    database.write_u32::<LittleEndian>(2).unwrap();
+    database.extend_from_slice(&entry);
+    database.extend_from_slice(&entry);
+
+    let parsed = ScoresDatabase::parse(&mut database.as_slice()).unwrap();
+    assert_eq!(parsed.scores(&header.beatmap_hash).len(), 2);
+    let best = parsed.best_score(&header.beatmap_hash).unwrap();
+    assert_eq!(best.score, header.score);
+    assert_eq!(best.score_id, header.score_id);
+}
+
+#[wasm_bindgen_test]
+fn test_parse_beatmapset() {
+    use corsace_parser::{
+        beatmap::beatmapset::{BeatmapsetFileKind, ParserBeatmapset},
+        replay::ParserError,
+    };
+    use std::io::Write;
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    let beatmap = include_bytes!("./beatmap.osu");
+    let mut osz = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, data) in [
+        ("beatmap.osu", beatmap.as_ref()),
+        ("broken.osu", b"not a beatmap".as_ref()),
+        ("audio.mp3", b"audio".as_ref()),
+        ("storyboard.osb", b"[Events]".as_ref()),
+        ("soft-hitclap.wav", b"clap".as_ref()),
+    ] {
+        osz.start_file(name, options).unwrap();
+        osz.write_all(data).unwrap();
+    }
+    let mut osz = osz.finish().unwrap().into_inner();
+
+    let result = corsace_parser::parse_beatmapset(&mut osz).unwrap();
+    assert_eq!(result.beatmaps.len(), 1);
+    assert_eq!(result.failed_beatmaps.len(), 1);
+    assert_eq!(result.failed_beatmaps[0].name, "broken.osu");
+    let kinds = result
+        .files
+        .iter()
+        .map(|file| file.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            BeatmapsetFileKind::Beatmap,
+            BeatmapsetFileKind::Beatmap,
+            BeatmapsetFileKind::Audio,
+            BeatmapsetFileKind::Storyboard,
+            BeatmapsetFileKind::Hitsound
+        ]
+    );
+    assert_eq!(result.files[0].hash, result.beatmaps[0].hash);
+    assert_eq!(result.missing_files, ["1134108.jpg"]);
+
+    // A crafted uncompressed size is rejected instead of allocated
+    let directory = osz
+        .windows(4)
+        .position(|signature| signature == b"PK\x01\x02")
+        .unwrap();
+    osz[directory + 24..directory + 28].copy_from_slice(&0x2000_0000u32.to_le_bytes());
+    let result = ParserBeatmapset::parse(std::io::Cursor::new(&osz));
+    assert!(matches!(
+        result,
+        Err(ParserError::ZipEntryTooLarge(name, _)) if name == "beatmap.osu"
+    ));
+}
+
+#[wasm_bindgen_test]
+fn test_parse_skin() {
+    use corsace_parser::{replay::ParserError, skin::ParserSkin};
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+
+    // Only the PNG header is needed for the dimensions
+    let png = |width: u32, height: u32| {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&width.to_be_bytes());
+        png.extend_from_slice(&height.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]);
+        png
+    };
+    let ini = "[General]\nName: test\nCursorExpand: 0\n\n[Colours]\nCombo1: 255,0,0\nCombo2: 0,255,0\n\n[Fonts]\nHitCirclePrefix: numbers\\default\n\n[Mania]\nKeys: 4\nHitPosition: 402\n\n[Mania]\nKeys: 7\n";
+    let mut osk = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, data) in [
+        ("skin.ini", ini.as_bytes().to_vec()),
+        ("hitcircle.png", png(128, 128)),
+        ("hitcircle@2x.png", png(300, 300)),
+        ("sliderb0@2x.png", png(256, 256)),
+    ] {
+        osk.start_file(name, FileOptions::default()).unwrap();
+        osk.write_all(&data).unwrap();
+    }
+    let mut osk = osk.finish().unwrap().into_inner();
+
+    let skin = corsace_parser::parse_skin(&mut osk).unwrap();
+    let ini = skin.ini.as_ref().unwrap();
+    assert_eq!(ini.general.name, "test");
+    assert!(!ini.general.cursor_expand);
+    assert_eq!(ini.colours.combo_colours.len(), 2);
+    assert_eq!(ini.fonts.hit_circle_prefix, "numbers/default");
+    assert_eq!(ini.mania.len(), 2);
+    assert_eq!(ini.mania[0].values["HitPosition"], "402");
+
+    let hitcircle = skin.element("hitcircle").unwrap();
+    assert_eq!(hitcircle.sd.as_ref().unwrap().width, Some(128));
+    assert_eq!(hitcircle.hd.as_ref().unwrap().height, Some(300));
+    assert_eq!(skin.oversized_elements.len(), 1);
+    assert_eq!(skin.oversized_elements[0].path, "hitcircle@2x.png");
+    assert!(!skin.missing_elements.contains(&"sliderb".to_string()));
+    assert!(skin
+        .missing_elements
+        .contains(&"numbers/default-0".to_string()));
+
+    // A crafted uncompressed size is rejected instead of allocated
+    let directory = osk
+        .windows(4)
+        .position(|signature| signature == b"PK\x01\x02")
+        .unwrap();
+    osk[directory + 24..directory + 28].copy_from_slice(&0x2000_0000u32.to_le_bytes());
+    let result = ParserSkin::parse(std::io::Cursor::new(&osk));
+    assert!(matches!(
+        result,
+        Err(ParserError::ZipEntryTooLarge(name, _)) if name == "skin.ini"
+    ));
+}
+
 // Benchmarks
 
 #[wasm_bindgen_test]